@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use goodrouter::router::Router;
+use goodrouter::router::{ParameterValueMatch, Router};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::HashSet;
@@ -11,7 +11,8 @@ criterion_group!(
     benches,
     router_parse_bench_small,
     router_parse_bench_docker,
-    router_parse_bench_github
+    router_parse_bench_github,
+    router_parse_bench_adversarial
 );
 criterion_main!(benches);
 
@@ -27,6 +28,35 @@ fn router_parse_bench_github(criterion: &mut Criterion) {
     setup_group(criterion, "github");
 }
 
+// a path with a lot of occurrences of the anchors but no match, when backtracking every
+// combination of occurrences could be tried
+fn router_parse_bench_adversarial(criterion: &mut Criterion) {
+    let mut router = Router::new();
+    router
+        .set_maximum_parameter_value_length(200)
+        .insert_route("z", "/{a}.{b}.{c}.{d}.{e}.{f}/z");
+
+    let path = format!("/{}", ".".repeat(120));
+
+    let mut group = criterion.benchmark_group("router parse adversarial");
+
+    for (parameter_value_match, label) in [
+        (ParameterValueMatch::FirstAnchor, "first anchor"),
+        (ParameterValueMatch::Shortest, "shortest"),
+        (ParameterValueMatch::Longest, "longest"),
+    ] {
+        router.set_parameter_value_match(parameter_value_match);
+
+        group.bench_function(label, |bencher| {
+            bencher.iter(|| {
+                router.parse_route(black_box(&path)).unwrap();
+            })
+        });
+    }
+
+    group.finish();
+}
+
 fn setup_group(criterion: &mut Criterion, name: &str) {
     let mut path = std::path::PathBuf::new();
     path.push("fixtures");
@@ -67,21 +97,31 @@ fn setup_group(criterion: &mut Criterion, name: &str) {
 
     let paths: Vec<_> = templates
         .iter()
-        .map(|template| router.stringify_route(template, &parameters).unwrap())
+        .map(|template| router.stringify_route(&template, &parameters).unwrap())
         .collect();
 
     let mut group = criterion.benchmark_group(format!("router parse {}", name));
 
-    group.bench_function(format!("{} routes", template_count), |bencher| {
-        let mut iteration = 0;
-        bencher.iter(|| {
-            let path = &paths[iteration % template_count];
-
-            router.parse_route(black_box(path));
-
-            iteration += 1;
-        })
-    });
+    // backtracking only costs something when the first occurrence of an anchor does not result
+    // in a match, so this shows the overhead for the common case
+    for (parameter_value_match, label) in [
+        (ParameterValueMatch::FirstAnchor, ""),
+        (ParameterValueMatch::Shortest, ", shortest"),
+        (ParameterValueMatch::Longest, ", longest"),
+    ] {
+        router.set_parameter_value_match(parameter_value_match);
+
+        group.bench_function(format!("{} routes{}", template_count, label), |bencher| {
+            let mut iteration = 0;
+            bencher.iter(|| {
+                let path = &paths[iteration % template_count];
+
+                router.parse_route(black_box(path)).unwrap();
+
+                iteration += 1;
+            })
+        });
+    }
 
     group.finish();
 }
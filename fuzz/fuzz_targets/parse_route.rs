@@ -0,0 +1,62 @@
+#![no_main]
+
+use goodrouter::router::{ParameterValueMatch, Router, TrailingSlash};
+use libfuzzer_sys::fuzz_target;
+use std::cell::RefCell;
+
+// the github templates and a few with non ascii anchors, so paths are matched against anchors
+// of different lengths in bytes and in chars
+const TEMPLATES: &str = include_str!("../../fixtures/github.txt");
+const EXTRA_TEMPLATES: [&str; 4] = ["/café/{x}/über", "/café/{x}", "/ü{x}ö", "/😀/{x}/{y}"];
+
+thread_local! {
+    static ROUTER: RefCell<Router<'static, &'static str>> = {
+        let mut router = Router::new();
+
+        for template in TEMPLATES
+            .split('\n')
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .chain(EXTRA_TEMPLATES)
+        {
+            router.insert_route(template, template);
+        }
+
+        RefCell::new(router)
+    };
+}
+
+// parsing any path with any options should never panic. The first two bytes are the options of
+// the router, the rest is the path.
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let (options, path) = data.split_at(2);
+
+    if let Ok(path) = std::str::from_utf8(path) {
+        ROUTER.with(|router| {
+            let mut router = router.borrow_mut();
+            router
+                .set_case_insensitive(options[0] & 1 != 0)
+                .set_collapse_duplicate_slashes(options[0] & 2 != 0)
+                .set_decode_unreserved(options[0] & 4 != 0)
+                .set_reject_encoded_slashes(options[0] & 8 != 0)
+                .set_trailing_slash(match (options[0] >> 4) & 3 {
+                    0 => TrailingSlash::Strict,
+                    1 => TrailingSlash::Ignore,
+                    _ => TrailingSlash::RedirectHint,
+                })
+                .set_parameter_value_match(match (options[0] >> 6) & 3 {
+                    0 => ParameterValueMatch::FirstAnchor,
+                    1 => ParameterValueMatch::Shortest,
+                    _ => ParameterValueMatch::Longest,
+                })
+                .set_maximum_parameter_value_length(usize::from(options[1] % 32) + 1);
+
+            let _ = router.parse_route(path);
+            let _ = router.parse_route_canonical(path);
+            let _ = router.parse_route_traced(path);
+        });
+    }
+});
@@ -0,0 +1,263 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use goodrouter::lint::{lint_templates, validate_template, LintMessage};
+use goodrouter::router::{Router, TrailingSlash};
+use goodrouter::template::TEMPLATE_PLACEHOLDER_REGEX;
+use goodrouter::typescript::generate_typescript;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Test, lint and visualize goodrouter route tables.
+///
+/// A route file has one template per line, the template is also the key of the route.
+#[derive(Parser)]
+#[command(name = "goodrouter", version)]
+struct Cli {
+    /// Maximum length of a parameter value when matching paths
+    #[arg(long, global = true, default_value_t = 20)]
+    maximum_parameter_value_length: usize,
+
+    /// Ignore ascii case when matching anchors
+    #[arg(long, global = true)]
+    case_insensitive: bool,
+
+    /// Treat multiple slashes as one when matching paths
+    #[arg(long, global = true)]
+    collapse_duplicate_slashes: bool,
+
+    /// Decode percent escapes of unreserved and non ascii characters before matching
+    #[arg(long, global = true)]
+    decode_unreserved: bool,
+
+    /// Do not match paths that contain an encoded slash
+    #[arg(long, global = true)]
+    reject_encoded_slashes: bool,
+
+    /// How to handle a trailing slash that does not match the template
+    #[arg(long, global = true, value_enum, default_value_t = TrailingSlashArg::Strict)]
+    trailing_slash: TrailingSlashArg,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TrailingSlashArg {
+    Strict,
+    Ignore,
+    RedirectHint,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Match a path and print the route key and parameters
+    Match {
+        /// File with one template per line
+        routes: PathBuf,
+        /// Path to match, like /product/1
+        path: String,
+        /// Print every node that was visited while matching to stderr
+        #[arg(long)]
+        trace: bool,
+    },
+    /// Build a path from a route key and parameters
+    Build {
+        /// File with one template per line
+        routes: PathBuf,
+        /// Route key, this is the template of the route
+        key: String,
+        /// Parameters as name=value pairs
+        parameters: Vec<String>,
+    },
+    /// Report malformed templates, ambiguous routes and shadowed routes
+    Lint {
+        /// File with one template per line
+        routes: PathBuf,
+    },
+    /// Print the compressed trie of the route table
+    Tree {
+        /// File with one template per line
+        routes: PathBuf,
+        /// Print a Graphviz DOT graph instead of a tree
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Print a TypeScript module with a typed stringifyRoute function for every route
+    Typescript {
+        /// File with one template per line
+        routes: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(exit_code) => exit_code,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, String> {
+    match &cli.command {
+        Command::Match {
+            routes,
+            path,
+            trace,
+        } => {
+            let content = read_routes(routes)?;
+            let templates = split_templates(&content);
+            let router = create_router(cli, &templates)?;
+
+            if *trace {
+                let (_route_key, _route_parameters, parse_trace) = router
+                    .parse_route_traced(path)
+                    .map_err(|error| error.to_string())?;
+                eprint!("{}", parse_trace);
+            }
+
+            let (route_key, route_parameters, canonical_path) = router
+                .parse_route_canonical(path)
+                .map_err(|error| error.to_string())?;
+            if let Some(canonical_path) = canonical_path {
+                eprintln!("canonical path is {}", canonical_path);
+            }
+            if let Some(route_key) = route_key {
+                println!("{}", route_key);
+
+                let mut route_parameters: Vec<_> = route_parameters.into_iter().collect();
+                route_parameters.sort();
+                for (name, value) in route_parameters {
+                    println!("{}={}", name, value);
+                }
+
+                Ok(ExitCode::SUCCESS)
+            } else {
+                eprintln!("no route matches {}", path);
+                Ok(ExitCode::FAILURE)
+            }
+        }
+        Command::Build {
+            routes,
+            key,
+            parameters,
+        } => {
+            let content = read_routes(routes)?;
+            let templates = split_templates(&content);
+            let router = create_router(cli, &templates)?;
+
+            let template = templates
+                .iter()
+                .cloned()
+                .find(|template| template == key)
+                .ok_or_else(|| format!("no route with key {}", key))?;
+
+            let route_parameters = parameters
+                .iter()
+                .map(|parameter| {
+                    parameter
+                        .split_once('=')
+                        .ok_or_else(|| format!("parameter {} is not a name=value pair", parameter))
+                })
+                .collect::<Result<HashMap<_, _>, _>>()?;
+
+            // every template in the router is valid
+            let parameter_names = validate_template(template, &TEMPLATE_PLACEHOLDER_REGEX).unwrap();
+            for parameter_name in parameter_names {
+                if !route_parameters.contains_key(parameter_name) {
+                    return Err(format!("missing parameter {}", parameter_name));
+                }
+            }
+
+            let path = router
+                .stringify_route(&template, &route_parameters)
+                .map_err(|error| error.to_string())?;
+            println!("{}", path);
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Lint { routes } => {
+            let content = read_routes(routes)?;
+            let templates = split_templates(&content);
+
+            let messages = lint_templates(&templates, &TEMPLATE_PLACEHOLDER_REGEX);
+            for message in messages.iter() {
+                println!("{}", message);
+            }
+
+            if messages.is_empty() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::FAILURE)
+            }
+        }
+        Command::Tree { routes, dot } => {
+            let content = read_routes(routes)?;
+            let templates = split_templates(&content);
+            let router = create_router(cli, &templates)?;
+
+            if *dot {
+                print!("{}", router.to_dot());
+            } else {
+                print!("{}", router.to_tree_string());
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Typescript { routes } => {
+            let content = read_routes(routes)?;
+            let templates = split_templates(&content);
+            let router = create_router(cli, &templates)?;
+
+            let module = generate_typescript(&router).map_err(|error| error.to_string())?;
+            print!("{}", module);
+
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn read_routes(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn split_templates(content: &str) -> Vec<&str> {
+    content
+        .split('\n')
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn create_router<'r>(cli: &Cli, templates: &[&'r str]) -> Result<Router<'r, &'r str>, String> {
+    // inserting ambiguous routes panics and malformed routes never match, so we check for them
+    // first
+    for message in lint_templates(templates, &TEMPLATE_PLACEHOLDER_REGEX) {
+        if let LintMessage::MalformedTemplate { .. } | LintMessage::AmbiguousRoute { .. } = message
+        {
+            return Err(format!("{}, run lint for more details", message));
+        }
+    }
+
+    let mut router = Router::new();
+    router
+        .set_maximum_parameter_value_length(cli.maximum_parameter_value_length)
+        .set_case_insensitive(cli.case_insensitive)
+        .set_collapse_duplicate_slashes(cli.collapse_duplicate_slashes)
+        .set_decode_unreserved(cli.decode_unreserved)
+        .set_reject_encoded_slashes(cli.reject_encoded_slashes)
+        .set_trailing_slash(match cli.trailing_slash {
+            TrailingSlashArg::Strict => TrailingSlash::Strict,
+            TrailingSlashArg::Ignore => TrailingSlash::Ignore,
+            TrailingSlashArg::RedirectHint => TrailingSlash::RedirectHint,
+        });
+
+    for template in templates.iter().cloned() {
+        router.insert_route(template, template);
+    }
+
+    Ok(router)
+}
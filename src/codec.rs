@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+use std::error::Error;
+
+// encode everything that is not unreserved, this is the default
+pub fn encode_component(value: &str) -> Cow<'_, str> {
+    urlencoding::encode(value)
+}
+
+pub fn decode_component(value: &str) -> Result<Cow<'_, str>, Box<dyn Error + Send + Sync>> {
+    Ok(urlencoding::decode(value)?)
+}
+
+// like `encode_component`, but slashes are kept as they are so a value can span multiple
+// segments of the path
+pub fn encode_segments(value: &str) -> Cow<'_, str> {
+    if !value.contains('/') {
+        return encode_component(value);
+    }
+
+    let segments: Vec<_> = value.split('/').map(encode_component).collect();
+    Cow::Owned(segments.join("/"))
+}
+
+// encode like the reserved expansion of rfc 6570, reserved characters and percent encoded
+// triplets are kept as they are
+pub fn encode_reserved(value: &str) -> Cow<'_, str> {
+    let bytes = value.as_bytes();
+    let is_kept = |index: usize| {
+        let byte = bytes[index];
+        byte.is_ascii_alphanumeric()
+            || b"-._~:/?#[]@!$&'()*+,;=".contains(&byte)
+            || byte == b'%'
+                && index + 2 < bytes.len()
+                && bytes[index + 1].is_ascii_hexdigit()
+                && bytes[index + 2].is_ascii_hexdigit()
+    };
+
+    if (0..bytes.len()).all(is_kept) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut encoded = String::with_capacity(value.len());
+    for (index, character) in value.char_indices() {
+        if is_kept(index) {
+            encoded.push(character);
+        } else {
+            encoded.push_str(&encode_component(character.encode_utf8(&mut [0; 4])));
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+// encode like a html form does, spaces become a plus sign
+pub fn encode_form(value: &str) -> Cow<'_, str> {
+    if !value.contains(' ') {
+        return encode_component(value);
+    }
+
+    let parts: Vec<_> = value.split(' ').map(encode_component).collect();
+    Cow::Owned(parts.join("+"))
+}
+
+pub fn decode_form(value: &str) -> Result<Cow<'_, str>, Box<dyn Error + Send + Sync>> {
+    if !value.contains('+') {
+        return decode_component(value);
+    }
+
+    Ok(Cow::Owned(
+        decode_component(&value.replace('+', " "))?.into_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_test() {
+        assert_eq!(encode_component("a/b c"), "a%2Fb%20c");
+        assert_eq!(decode_component("a%2Fb%20c").unwrap(), "a/b c");
+        assert!(decode_component("%FF").is_err());
+
+        assert_eq!(encode_segments("a/b c/d"), "a/b%20c/d");
+        assert_eq!(decode_component("a/b%20c/d").unwrap(), "a/b c/d");
+
+        assert_eq!(encode_reserved("a/b c?d=%20&e=%"), "a/b%20c?d=%20&e=%25");
+        assert_eq!(decode_component("a/b%20c?d=%20").unwrap(), "a/b c?d= ");
+
+        assert_eq!(encode_form("a b+c"), "a+b%2Bc");
+        assert_eq!(decode_form("a+b%2Bc").unwrap(), "a b+c");
+    }
+}
@@ -1,4 +1,10 @@
+pub mod codec;
+pub mod lint;
+#[cfg(feature = "openapi")]
+pub mod openapi;
 mod route_node;
 pub mod router;
 mod string_utility;
-mod template;
+pub mod template;
+pub mod trace;
+pub mod typescript;
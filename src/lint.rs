@@ -0,0 +1,295 @@
+use crate::router::Router;
+use crate::template::template_pairs::parse_template_pairs;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateProblem<'r> {
+    // a `{` or `}` that is not part of a parameter placeholder
+    UnbalancedBrace,
+    // a placeholder without a name, like `{}`
+    EmptyParameterName,
+    // two placeholders without an anchor in between, like `{a}{b}`
+    AdjacentParameters(&'r str, &'r str),
+    // the same parameter name is used more than once in a template
+    DuplicateParameterName(&'r str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintMessage<'r> {
+    // the template cannot be inserted into a router
+    MalformedTemplate {
+        template: &'r str,
+        problem: TemplateProblem<'r>,
+    },
+    // both templates result in the same route node, inserting both will panic
+    AmbiguousRoute {
+        template: &'r str,
+        other_template: &'r str,
+    },
+    // a path that was generated from this template parses to another route
+    ShadowedRoute {
+        template: &'r str,
+        shadowing_template: &'r str,
+    },
+    // a path that was generated from this template does not parse at all
+    UnreachableRoute {
+        template: &'r str,
+    },
+}
+
+impl<'r> fmt::Display for TemplateProblem<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateProblem::UnbalancedBrace => write!(f, "unbalanced brace"),
+            TemplateProblem::EmptyParameterName => write!(f, "empty parameter name"),
+            TemplateProblem::AdjacentParameters(left, right) => write!(
+                f,
+                "parameters {{{}}} and {{{}}} are not separated by an anchor",
+                left, right
+            ),
+            TemplateProblem::DuplicateParameterName(name) => {
+                write!(f, "duplicate parameter name {{{}}}", name)
+            }
+        }
+    }
+}
+
+impl<'r> fmt::Display for LintMessage<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintMessage::MalformedTemplate { template, problem } => {
+                write!(f, "malformed template {}: {}", template, problem)
+            }
+            LintMessage::AmbiguousRoute {
+                template,
+                other_template,
+            } => write!(
+                f,
+                "ambiguous route {}: conflicts with {}",
+                template, other_template
+            ),
+            LintMessage::ShadowedRoute {
+                template,
+                shadowing_template,
+            } => write!(
+                f,
+                "shadowed route {}: paths are matched by {}",
+                template, shadowing_template
+            ),
+            LintMessage::UnreachableRoute { template } => {
+                write!(f, "unreachable route {}", template)
+            }
+        }
+    }
+}
+
+pub fn lint_templates<'r>(
+    templates: &[&'r str],
+    parameter_placeholder_re: &'r Regex,
+) -> Vec<LintMessage<'r>> {
+    let mut messages = Vec::new();
+
+    // templates that are not malformed and not ambiguous, these are safe to insert in a router
+    let mut valid_templates = Vec::new();
+    // the shape of a template is the list of anchors and if they are preceded by a parameter
+    let mut shapes: HashMap<Vec<(&str, bool)>, &str> = HashMap::new();
+
+    for template in templates.iter().cloned() {
+        if let Err(problem) = validate_template(template, parameter_placeholder_re) {
+            messages.push(LintMessage::MalformedTemplate { template, problem });
+            continue;
+        }
+
+        let shape: Vec<_> = parse_template_pairs(template, parameter_placeholder_re)
+            .map(|(anchor, parameter)| (anchor, parameter.is_some()))
+            .collect();
+
+        if let Some(other_template) = shapes.get(&shape) {
+            messages.push(LintMessage::AmbiguousRoute {
+                template,
+                other_template,
+            });
+            continue;
+        }
+
+        shapes.insert(shape, template);
+        valid_templates.push(template);
+    }
+
+    // generate a unique value for every parameter name, this is what the tests do as well
+    let mut parameter_names: Vec<&str> = valid_templates
+        .iter()
+        .flat_map(|template| parse_template_pairs(template, parameter_placeholder_re))
+        .filter_map(|(_anchor, parameter)| parameter)
+        .collect();
+    parameter_names.sort_unstable();
+    parameter_names.dedup();
+
+    let parameter_values: Vec<_> = (0..parameter_names.len())
+        .map(|index| format!("p{}", index))
+        .collect();
+
+    let parameters: HashMap<_, _> = parameter_names
+        .into_iter()
+        .zip(parameter_values.iter().map(|value| value.as_str()))
+        .collect();
+
+    let mut router = Router::new();
+    router.set_parameter_placeholder_re(parameter_placeholder_re);
+    for template in valid_templates.iter().cloned() {
+        router.insert_route(template, template);
+    }
+
+    for template in valid_templates.iter().cloned() {
+        let path = router.stringify_route(&template, &parameters).unwrap();
+        // the generated parameter values are always decoded without errors
+        let (route_key, _route_parameters) = router.parse_route(&path).unwrap();
+
+        match route_key {
+            Some(&route_key) if route_key == template => {}
+            Some(&route_key) => messages.push(LintMessage::ShadowedRoute {
+                template,
+                shadowing_template: route_key,
+            }),
+            None => messages.push(LintMessage::UnreachableRoute { template }),
+        }
+    }
+
+    messages
+}
+
+// check if a template can be inserted into a router, returns the names of the parameters in the
+// order they appear in the template
+pub fn validate_template<'r>(
+    template: &'r str,
+    parameter_placeholder_re: &'r Regex,
+) -> Result<Vec<&'r str>, TemplateProblem<'r>> {
+    let remainder = parameter_placeholder_re.replace_all(template, "");
+    if remainder.contains('{') || remainder.contains('}') {
+        return Err(TemplateProblem::UnbalancedBrace);
+    }
+
+    let pairs: Vec<_> = parse_template_pairs(template, parameter_placeholder_re).collect();
+
+    let mut parameter_names = Vec::new();
+    for index in 0..pairs.len() {
+        let (anchor, parameter) = pairs[index];
+
+        if let Some(parameter) = parameter {
+            if parameter.is_empty() {
+                return Err(TemplateProblem::EmptyParameterName);
+            }
+
+            if parameter_names.contains(&parameter) {
+                return Err(TemplateProblem::DuplicateParameterName(parameter));
+            }
+            parameter_names.push(parameter);
+
+            // an empty anchor means the parameter matches the rest of the path, that only works
+            // for the last parameter
+            if anchor.is_empty() && index < pairs.len() - 1 {
+                let (_anchor, next_parameter) = pairs[index + 1];
+                return Err(TemplateProblem::AdjacentParameters(
+                    parameter,
+                    next_parameter.unwrap(),
+                ));
+            }
+        }
+    }
+
+    Ok(parameter_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TEMPLATE_PLACEHOLDER_REGEX;
+
+    #[test]
+    fn lint_templates_test() {
+        let messages = lint_templates(
+            &[
+                "/a",
+                "/b/{x}",
+                "/b/{y}",
+                "/c/{x}{y}",
+                "/d/{x}/{x}",
+                "/e/{}",
+                "/f/{x",
+                "/g/{x}",
+                "/g/{x}p",
+            ],
+            &TEMPLATE_PLACEHOLDER_REGEX,
+        );
+
+        assert_eq!(
+            messages,
+            vec![
+                LintMessage::AmbiguousRoute {
+                    template: "/b/{y}",
+                    other_template: "/b/{x}",
+                },
+                LintMessage::MalformedTemplate {
+                    template: "/c/{x}{y}",
+                    problem: TemplateProblem::AdjacentParameters("x", "y"),
+                },
+                LintMessage::MalformedTemplate {
+                    template: "/d/{x}/{x}",
+                    problem: TemplateProblem::DuplicateParameterName("x"),
+                },
+                LintMessage::MalformedTemplate {
+                    template: "/e/{}",
+                    problem: TemplateProblem::EmptyParameterName,
+                },
+                LintMessage::MalformedTemplate {
+                    template: "/f/{x",
+                    problem: TemplateProblem::UnbalancedBrace,
+                },
+                LintMessage::ShadowedRoute {
+                    template: "/g/{x}p",
+                    shadowing_template: "/g/{x}",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_template_test() {
+        assert_eq!(
+            validate_template("/a/{b}/c/{d}", &TEMPLATE_PLACEHOLDER_REGEX),
+            Ok(vec!["b", "d"])
+        );
+        assert_eq!(
+            validate_template("/a", &TEMPLATE_PLACEHOLDER_REGEX),
+            Ok(vec![])
+        );
+        assert_eq!(
+            validate_template("/a/{b}{c}", &TEMPLATE_PLACEHOLDER_REGEX),
+            Err(TemplateProblem::AdjacentParameters("b", "c"))
+        );
+    }
+
+    #[test]
+    fn lint_templates_fixtures() {
+        for name in ["small", "docker", "github"] {
+            let mut path = std::path::PathBuf::new();
+            path.push("fixtures");
+            path.push(name);
+            path.set_extension("txt");
+
+            let templates = std::fs::read_to_string(path.as_path()).unwrap();
+            let templates: Vec<_> = templates
+                .split('\n')
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .collect();
+
+            assert_eq!(
+                lint_templates(&templates, &TEMPLATE_PLACEHOLDER_REGEX),
+                vec![]
+            );
+        }
+    }
+}
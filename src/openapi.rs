@@ -0,0 +1,758 @@
+use crate::codec::decode_component;
+use crate::router::{InvalidParameterValue, Router};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::hash::Hash;
+use std::path::Path;
+
+// the methods of a path item, in the order of the specification. The first operation of a path
+// is the one that parsing returns as the route key.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Debug)]
+pub enum OpenApiError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml_ng::Error),
+    // the document has no `paths` object
+    MissingPaths,
+    // every operation needs an `operationId`, that is the route key
+    MissingOperationId {
+        template: String,
+        method: String,
+    },
+    // a `$ref` that does not point to anything in the document
+    InvalidReference(String),
+    // a `pattern` in a schema that is not a valid regular expression
+    InvalidPattern(String),
+    // an `operationId` that is used more than once, or that is already a route key
+    DuplicateOperationId(String),
+    // two paths that match the same paths, like `/a/{x}` and `/a/{y}`
+    AmbiguousPath {
+        template: String,
+        operation_id: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenApiDocument {
+    pub paths: Vec<OpenApiPath>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenApiPath {
+    pub template: String,
+    // never empty, all paths without operations are skipped
+    pub operations: Vec<OpenApiOperation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenApiOperation {
+    pub method: String,
+    pub operation_id: String,
+    // only the path parameters, path level parameters are included
+    pub parameters: Vec<OpenApiParameter>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenApiParameter {
+    pub name: String,
+    pub constraints: Vec<ParameterConstraint>,
+}
+
+// the parts of a schema that we can check when parsing a route
+#[derive(Debug, Clone)]
+pub enum ParameterConstraint {
+    Integer,
+    Number,
+    Boolean,
+    Enum(Vec<String>),
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Regex),
+}
+
+impl fmt::Display for OpenApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenApiError::Io(error) => write!(f, "could not read document: {}", error),
+            OpenApiError::Json(error) => write!(f, "invalid json: {}", error),
+            OpenApiError::Yaml(error) => write!(f, "invalid yaml: {}", error),
+            OpenApiError::MissingPaths => write!(f, "document has no paths"),
+            OpenApiError::MissingOperationId { template, method } => {
+                write!(f, "operation {} {} has no operationId", method, template)
+            }
+            OpenApiError::InvalidReference(reference) => {
+                write!(f, "invalid reference {}", reference)
+            }
+            OpenApiError::InvalidPattern(pattern) => write!(f, "invalid pattern {}", pattern),
+            OpenApiError::DuplicateOperationId(operation_id) => {
+                write!(f, "duplicate operationId {}", operation_id)
+            }
+            OpenApiError::AmbiguousPath {
+                template,
+                operation_id,
+            } => write!(
+                f,
+                "path {} matches the same paths as operation {}",
+                template, operation_id
+            ),
+        }
+    }
+}
+
+impl Error for OpenApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OpenApiError::Io(error) => Some(error),
+            OpenApiError::Json(error) => Some(error),
+            OpenApiError::Yaml(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParameterConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterConstraint::Integer => write!(f, "an integer"),
+            ParameterConstraint::Number => write!(f, "a number"),
+            ParameterConstraint::Boolean => write!(f, "a boolean"),
+            ParameterConstraint::Enum(values) => write!(f, "one of {}", values.join(", ")),
+            ParameterConstraint::MinLength(length) => {
+                write!(f, "at least {} characters", length)
+            }
+            ParameterConstraint::MaxLength(length) => {
+                write!(f, "at most {} characters", length)
+            }
+            ParameterConstraint::Pattern(re) => write!(f, "matching {}", re),
+        }
+    }
+}
+
+impl ParameterConstraint {
+    pub fn is_satisfied(&self, value: &str) -> bool {
+        match self {
+            ParameterConstraint::Integer => value.parse::<i64>().is_ok(),
+            ParameterConstraint::Number => value.parse::<f64>().is_ok(),
+            ParameterConstraint::Boolean => value == "true" || value == "false",
+            ParameterConstraint::Enum(values) => values.iter().any(|allowed| allowed == value),
+            ParameterConstraint::MinLength(length) => value.chars().count() >= *length,
+            ParameterConstraint::MaxLength(length) => value.chars().count() <= *length,
+            // patterns are not anchored, as in json schema
+            ParameterConstraint::Pattern(re) => re.is_match(value),
+        }
+    }
+}
+
+impl OpenApiDocument {
+    // insert a route for every path, every `operationId` of the path is a key of the route that
+    // can be stringified, parsing returns the `operationId` of the first operation. The parameter
+    // values are decoded with `decode_component` and then checked against the constraints, a
+    // violation results in a `ParseError::InvalidParameterValue` and a value that cannot be
+    // decoded in a `ParseError::InvalidParameterEncoding`. On an error the routes of the paths
+    // before the failing one stay in the router.
+    pub fn insert_routes<'r, M>(
+        &'r self,
+        router: &mut Router<'r, &'r str, M>,
+    ) -> Result<(), OpenApiError> {
+        for path in self.paths.iter() {
+            for operation in path.operations.iter() {
+                if router.contains_route(&operation.operation_id.as_str()) {
+                    return Err(OpenApiError::DuplicateOperationId(
+                        operation.operation_id.clone(),
+                    ));
+                }
+            }
+
+            let operation = &path.operations[0];
+            router
+                .try_insert_route(&operation.operation_id, &path.template)
+                .map_err(|other_route_key| OpenApiError::AmbiguousPath {
+                    template: path.template.clone(),
+                    operation_id: other_route_key.to_owned(),
+                })?;
+            for other_operation in path.operations[1..].iter() {
+                router.insert_route_alias(&other_operation.operation_id, &operation.operation_id);
+            }
+
+            for parameter in operation.parameters.iter() {
+                if parameter.constraints.is_empty() {
+                    continue;
+                }
+
+                let constraints = parameter.constraints.clone();
+                router.set_route_named_parameter_value_decoder(
+                    &operation.operation_id,
+                    &parameter.name,
+                    Box::new(move |value| {
+                        let value = decode_component(value)?;
+                        check_constraints(&constraints, &value)?;
+                        Ok(value)
+                    }),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // find an operation of the path that `route_key` is an `operationId` of, this is how to get
+    // from the route key that parsing returns to the operation of a method
+    pub fn find_operation(&self, route_key: &str, method: &str) -> Option<&OpenApiOperation> {
+        self.paths
+            .iter()
+            .find(|path| {
+                path.operations
+                    .iter()
+                    .any(|operation| operation.operation_id == route_key)
+            })?
+            .operations
+            .iter()
+            .find(|operation| operation.method.eq_ignore_ascii_case(method))
+    }
+}
+
+fn check_constraints(
+    constraints: &[ParameterConstraint],
+    value: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for constraint in constraints.iter() {
+        if !constraint.is_satisfied(value) {
+            return Err(InvalidParameterValue(format!("{:?} is not {}", value, constraint)).into());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load_openapi_document(path: impl AsRef<Path>) -> Result<OpenApiDocument, OpenApiError> {
+    let source = std::fs::read_to_string(path).map_err(OpenApiError::Io)?;
+    parse_openapi_document(&source)
+}
+
+// parse a json or yaml document. A document that starts with a brace is parsed as json, not as
+// yaml, because not every json document is valid yaml, think of tabs.
+pub fn parse_openapi_document(source: &str) -> Result<OpenApiDocument, OpenApiError> {
+    let root: Value = if source.trim_start().starts_with('{') {
+        serde_json::from_str(source).map_err(OpenApiError::Json)?
+    } else {
+        serde_yaml_ng::from_str(source).map_err(OpenApiError::Yaml)?
+    };
+
+    let path_items = root
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or(OpenApiError::MissingPaths)?;
+
+    let mut paths = Vec::new();
+    let mut operation_ids = HashSet::new();
+    for (template, path_item) in path_items.iter() {
+        let path_item = resolve_reference(&root, path_item)?;
+        let path_parameters = parse_parameters(&root, path_item.get("parameters"))?;
+
+        let mut operations = Vec::new();
+        for method in METHODS {
+            let operation = match path_item.get(method) {
+                Some(operation) => resolve_reference(&root, operation)?,
+                None => continue,
+            };
+
+            let operation_id = operation
+                .get("operationId")
+                .and_then(Value::as_str)
+                .ok_or_else(|| OpenApiError::MissingOperationId {
+                    template: template.clone(),
+                    method: method.to_owned(),
+                })?;
+            if !operation_ids.insert(operation_id) {
+                return Err(OpenApiError::DuplicateOperationId(operation_id.to_owned()));
+            }
+
+            // operation parameters override path parameters with the same name
+            let mut parameters = parse_parameters(&root, operation.get("parameters"))?;
+            for path_parameter in path_parameters.iter() {
+                if !parameters
+                    .iter()
+                    .any(|parameter| parameter.name == path_parameter.name)
+                {
+                    parameters.push(path_parameter.clone());
+                }
+            }
+
+            operations.push(OpenApiOperation {
+                method: method.to_owned(),
+                operation_id: operation_id.to_owned(),
+                parameters,
+            });
+        }
+
+        if !operations.is_empty() {
+            paths.push(OpenApiPath {
+                template: template.clone(),
+                operations,
+            });
+        }
+    }
+
+    Ok(OpenApiDocument { paths })
+}
+
+fn parse_parameters(
+    root: &Value,
+    parameters: Option<&Value>,
+) -> Result<Vec<OpenApiParameter>, OpenApiError> {
+    let mut result = Vec::new();
+
+    for parameter in parameters
+        .and_then(Value::as_array)
+        .map(|parameters| parameters.as_slice())
+        .unwrap_or_default()
+    {
+        let parameter = resolve_reference(root, parameter)?;
+        if parameter.get("in").and_then(Value::as_str) != Some("path") {
+            continue;
+        }
+
+        let name = match parameter.get("name").and_then(Value::as_str) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+
+        let constraints = match parameter.get("schema") {
+            Some(schema) => parse_constraints(resolve_reference(root, schema)?)?,
+            None => Vec::new(),
+        };
+
+        result.push(OpenApiParameter { name, constraints });
+    }
+
+    Ok(result)
+}
+
+fn parse_constraints(schema: &Value) -> Result<Vec<ParameterConstraint>, OpenApiError> {
+    let mut constraints = Vec::new();
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("integer") => constraints.push(ParameterConstraint::Integer),
+        Some("number") => constraints.push(ParameterConstraint::Number),
+        Some("boolean") => constraints.push(ParameterConstraint::Boolean),
+        _ => {}
+    }
+
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        constraints.push(ParameterConstraint::Enum(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+                .collect(),
+        ));
+    }
+
+    if let Some(length) = schema.get("minLength").and_then(Value::as_u64) {
+        constraints.push(ParameterConstraint::MinLength(length as usize));
+    }
+
+    if let Some(length) = schema.get("maxLength").and_then(Value::as_u64) {
+        constraints.push(ParameterConstraint::MaxLength(length as usize));
+    }
+
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        let re =
+            Regex::new(pattern).map_err(|_| OpenApiError::InvalidPattern(pattern.to_owned()))?;
+        constraints.push(ParameterConstraint::Pattern(re));
+    }
+
+    Ok(constraints)
+}
+
+// follow local references like `#/components/parameters/id`, references to other documents are
+// not supported
+fn resolve_reference<'v>(root: &'v Value, value: &'v Value) -> Result<&'v Value, OpenApiError> {
+    let mut value = value;
+
+    // a limit so a reference cycle does not loop forever
+    for _ in 0..32 {
+        let reference = match value.get("$ref").and_then(Value::as_str) {
+            Some(reference) => reference,
+            None => return Ok(value),
+        };
+
+        value = reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .ok_or_else(|| OpenApiError::InvalidReference(reference.to_owned()))?;
+    }
+
+    Err(OpenApiError::InvalidReference(
+        value["$ref"].as_str().unwrap_or_default().to_owned(),
+    ))
+}
+
+// export the routes of a router as the `paths` object of an openapi document. The router does
+// not know about methods, so every route becomes a `get` operation with the route key as the
+// `operationId`. The aliases of a route, like the other operations of an imported path, get the
+// next methods in the order of the specification, so importing and exporting a document keeps
+// every `operationId` but not the methods. All parameters are required path parameters of type
+// string.
+pub fn export_openapi_paths<K: Eq + Hash + Clone + Display, M>(router: &Router<K, M>) -> Value {
+    let mut paths = Map::new();
+
+    for (route_key, template, route_parameter_names) in router.routes() {
+        let mut aliases: Vec<_> = router
+            .route_aliases_of(route_key)
+            .into_iter()
+            .map(|alias| alias.to_string())
+            .collect();
+        aliases.sort();
+
+        let parameters: Vec<_> = route_parameter_names
+            .iter()
+            .map(|parameter_name| {
+                json!({
+                    "name": parameter_name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+
+        // aliases are only inserted for the other operations of a path, so there is a method
+        // for every one of them
+        let path_item: Map<_, _> = METHODS
+            .iter()
+            .zip(std::iter::once(route_key.to_string()).chain(aliases))
+            .map(|(method, operation_id)| {
+                (
+                    method.to_string(),
+                    json!({
+                        "operationId": operation_id,
+                        "parameters": parameters,
+                    }),
+                )
+            })
+            .collect();
+
+        paths.insert(template, Value::Object(path_item));
+    }
+
+    Value::Object(paths)
+}
+
+// export the routes of a router as a list of objects with the route key, the other keys of the
+// route, the template and the names of the parameters
+pub fn export_route_list<K: Eq + Hash + Clone + Serialize, M>(router: &Router<K, M>) -> Value {
+    Value::Array(
+        router
+            .routes()
+            .into_iter()
+            .map(|(route_key, template, route_parameter_names)| {
+                let mut aliases: Vec<_> = router
+                    .route_aliases_of(route_key)
+                    .into_iter()
+                    .map(|alias| json!(alias))
+                    .collect();
+                aliases.sort_by_key(|alias| alias.to_string());
+
+                json!({
+                    "key": route_key,
+                    "aliases": aliases,
+                    "template": template,
+                    "parameters": route_parameter_names,
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::ParseError;
+    use std::collections::HashMap;
+
+    #[test]
+    fn openapi_fixture() {
+        let document = load_openapi_document("fixtures/petstore.yaml").unwrap();
+
+        let route_keys: Vec<_> = document
+            .paths
+            .iter()
+            .map(|path| path.operations[0].operation_id.as_str())
+            .collect();
+        assert_eq!(
+            route_keys,
+            vec!["listPets", "showPetById", "showPetPhoto", "showOwner"]
+        );
+
+        let mut router = Router::new();
+        document.insert_routes(&mut router).unwrap();
+
+        let (route_key, route_parameters) = router.parse_route("/pets/12").unwrap();
+        assert_eq!(route_key, Some(&"showPetById"));
+        assert_eq!(route_parameters["petId"], "12");
+
+        // every operation is a key of the route of the path
+        let route_parameters = vec![("petId", "12")].into_iter().collect();
+        assert_eq!(
+            router.stringify_route(&"deletePet", &route_parameters),
+            Ok("/pets/12".into())
+        );
+        assert_eq!(
+            router.stringify_route(&"createPet", &HashMap::new()),
+            Ok("/pets".into())
+        );
+        assert!(router.contains_route(&"deletePet"));
+        assert_eq!(router.len(), 4);
+
+        assert_eq!(
+            document
+                .find_operation("showPetById", "DELETE")
+                .unwrap()
+                .operation_id,
+            "deletePet"
+        );
+        assert!(document.find_operation("showPetById", "put").is_none());
+        assert_eq!(
+            document
+                .find_operation("deletePet", "get")
+                .unwrap()
+                .operation_id,
+            "showPetById"
+        );
+
+        assert_eq!(
+            router.parse_route("/pets/twelve"),
+            Err(ParseError::InvalidParameterValue {
+                name: "petId",
+                value: "twelve".to_owned(),
+                reason: "\"twelve\" is not an integer".to_owned(),
+            })
+        );
+        // a value that cannot be decoded is still an encoding error
+        assert_eq!(
+            router.parse_route("/pets/%FF"),
+            Err(ParseError::InvalidParameterEncoding {
+                name: "petId",
+                value: "%FF".to_owned(),
+            })
+        );
+
+        let (route_key, route_parameters) = router.parse_route("/pets/12/photos/large").unwrap();
+        assert_eq!(route_key, Some(&"showPetPhoto"));
+        assert_eq!(route_parameters["size"], "large");
+        assert!(router.parse_route("/pets/12/photos/medium").is_err());
+
+        let (route_key, _route_parameters) = router.parse_route("/owners/elmer").unwrap();
+        assert_eq!(route_key, Some(&"showOwner"));
+        assert!(router.parse_route("/owners/Elmer").is_err());
+        assert!(router.parse_route("/owners/bartholomew").is_err());
+    }
+
+    #[test]
+    fn openapi_json() {
+        let document = parse_openapi_document(
+            r#"{
+                "openapi": "3.0.3",
+                "paths": {
+                    "/a/{id}": {
+                        "get": {
+                            "operationId": "getA",
+                            "parameters": [
+                                { "name": "id", "in": "path", "schema": { "type": "boolean" } }
+                            ]
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut router = Router::new();
+        document.insert_routes(&mut router).unwrap();
+
+        assert_eq!(router.parse_route("/a/true").unwrap().0, Some(&"getA"));
+        assert!(router.parse_route("/a/yes").is_err());
+
+        // an operation with the same id cannot be inserted twice
+        assert!(matches!(
+            document.insert_routes(&mut router),
+            Err(OpenApiError::DuplicateOperationId(operation_id)) if operation_id == "getA"
+        ));
+
+        assert!(matches!(
+            parse_openapi_document(r#"{ "paths": { "/b": { "get": {} } } }"#),
+            Err(OpenApiError::MissingOperationId { .. })
+        ));
+
+        assert!(matches!(
+            parse_openapi_document(
+                r#"{ "paths": {
+                    "/b": { "get": { "operationId": "b" } },
+                    "/c": { "get": { "operationId": "b" } }
+                } }"#
+            ),
+            Err(OpenApiError::DuplicateOperationId(operation_id)) if operation_id == "b"
+        ));
+    }
+
+    #[test]
+    fn openapi_ambiguous_paths() {
+        let document = parse_openapi_document(
+            r#"{ "paths": {
+                "/a/{x}": { "get": { "operationId": "getX" } },
+                "/a/{y}": { "get": { "operationId": "getY" } }
+            } }"#,
+        )
+        .unwrap();
+
+        let mut router = Router::new();
+        assert!(matches!(
+            document.insert_routes(&mut router),
+            Err(OpenApiError::AmbiguousPath { template, operation_id })
+                if template == "/a/{y}" && operation_id == "getX"
+        ));
+
+        // the routes before the ambiguous path are inserted
+        assert_eq!(router.parse_route("/a/1").unwrap().0, Some(&"getX"));
+        assert!(!router.contains_route(&"getY"));
+    }
+
+    #[test]
+    fn openapi_export() {
+        let mut router = Router::new();
+        router
+            .insert_route("user", "/users/{id}")
+            .insert_route("users", "/users")
+            .insert_route("post", "/users/{id}/posts/{post}");
+
+        assert_eq!(
+            export_openapi_paths(&router),
+            json!({
+                "/users": {
+                    "get": { "operationId": "users", "parameters": [] },
+                },
+                "/users/{id}": {
+                    "get": {
+                        "operationId": "user",
+                        "parameters": [
+                            {
+                                "name": "id",
+                                "in": "path",
+                                "required": true,
+                                "schema": { "type": "string" },
+                            },
+                        ],
+                    },
+                },
+                "/users/{id}/posts/{post}": {
+                    "get": {
+                        "operationId": "post",
+                        "parameters": [
+                            {
+                                "name": "id",
+                                "in": "path",
+                                "required": true,
+                                "schema": { "type": "string" },
+                            },
+                            {
+                                "name": "post",
+                                "in": "path",
+                                "required": true,
+                                "schema": { "type": "string" },
+                            },
+                        ],
+                    },
+                },
+            })
+        );
+
+        assert_eq!(
+            export_route_list(&router),
+            json!([
+                { "key": "users", "aliases": [], "template": "/users", "parameters": [] },
+                {
+                    "key": "user",
+                    "aliases": [],
+                    "template": "/users/{id}",
+                    "parameters": ["id"],
+                },
+                {
+                    "key": "post",
+                    "aliases": [],
+                    "template": "/users/{id}/posts/{post}",
+                    "parameters": ["id", "post"],
+                },
+            ])
+        );
+
+        // an exported document can be imported again
+        let source = json!({ "openapi": "3.0.3", "paths": export_openapi_paths(&router) });
+        let document = parse_openapi_document(&source.to_string()).unwrap();
+
+        let mut imported_router = Router::new();
+        document.insert_routes(&mut imported_router).unwrap();
+
+        let (route_key, route_parameters) =
+            imported_router.parse_route("/users/1/posts/2").unwrap();
+        assert_eq!(route_key, Some(&"post"));
+        assert_eq!(route_parameters["post"], "2");
+    }
+
+    #[test]
+    fn openapi_export_aliases() {
+        let document = load_openapi_document("fixtures/petstore.yaml").unwrap();
+        let mut router = Router::new();
+        document.insert_routes(&mut router).unwrap();
+
+        let paths = export_openapi_paths(&router);
+        assert_eq!(paths["/pets"]["get"]["operationId"], "listPets");
+        assert_eq!(paths["/pets"]["put"]["operationId"], "createPet");
+        assert_eq!(paths["/pets/{petId}"]["get"]["operationId"], "showPetById");
+        assert_eq!(paths["/pets/{petId}"]["put"]["operationId"], "deletePet");
+
+        let route_list = export_route_list(&router);
+        assert_eq!(route_list[1]["key"], "listPets");
+        assert_eq!(route_list[1]["aliases"], json!(["createPet"]));
+
+        // every operationId survives importing the exported document, the methods do not
+        let source = json!({ "openapi": "3.0.3", "paths": paths });
+        let exported_document = parse_openapi_document(&source.to_string()).unwrap();
+
+        let operation_ids = |document: &OpenApiDocument| {
+            let mut operation_ids: Vec<_> = document
+                .paths
+                .iter()
+                .flat_map(|path| path.operations.iter())
+                .map(|operation| operation.operation_id.clone())
+                .collect();
+            operation_ids.sort();
+            operation_ids
+        };
+        assert_eq!(operation_ids(&exported_document), operation_ids(&document));
+
+        let mut imported_router = Router::new();
+        exported_document
+            .insert_routes(&mut imported_router)
+            .unwrap();
+        let route_parameters = vec![("petId", "12")].into_iter().collect();
+        assert_eq!(
+            imported_router.stringify_route(&"deletePet", &route_parameters),
+            Ok("/pets/12".into())
+        );
+        assert_eq!(
+            imported_router.parse_route("/pets/12").unwrap().0,
+            Some(&"showPetById")
+        );
+    }
+}
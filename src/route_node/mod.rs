@@ -1,4 +1,5 @@
 pub mod route_node_merge;
+pub mod route_node_print;
 pub mod route_node_rc;
 pub mod route_node_utility;
 
@@ -19,10 +20,17 @@ pub struct RouteNode<'r, K> {
     pub route_key: Option<K>,
     // the route parameter names
     pub route_parameter_names: Vec<&'r str>,
+    // index of the route in the router, to find things that belong to the route without a lookup
+    // by the route's key
+    pub route_index: usize,
     // suffix that comes after the parameter value (if any!) of the path
     anchor: &'r str,
     // does this node has a parameter
     has_parameter: bool,
+    // characters that the parameter value (if any!) may not contain
+    parameter_value_separators: &'r str,
+    // the highest priority of the routes of this node and it's descendants
+    priority: u32,
     // children that represent the rest of the path that needs to be matched
     children: BTreeSet<RouteNodeRc<'r, K>>,
     // parent node, should only be null for the root node
@@ -31,6 +39,14 @@ pub struct RouteNode<'r, K> {
 
 impl<'r, K> Ord for RouteNode<'r, K> {
     fn cmp(&self, other: &Self) -> Ordering {
+        // an explicit priority overrides all other rules
+        if self.priority > other.priority {
+            return Ordering::Less;
+        }
+        if self.priority < other.priority {
+            return Ordering::Greater;
+        }
+
         if self.anchor.len() < other.anchor.len() {
             return Ordering::Greater;
         }
@@ -45,6 +61,23 @@ impl<'r, K> Ord for RouteNode<'r, K> {
             return Ordering::Greater;
         }
 
+        // a parameter that may not contain separators is more specific
+        if !self.parameter_value_separators.is_empty()
+            && other.parameter_value_separators.is_empty()
+        {
+            return Ordering::Less;
+        }
+        if self.parameter_value_separators.is_empty()
+            && !other.parameter_value_separators.is_empty()
+        {
+            return Ordering::Greater;
+        }
+        if self.parameter_value_separators != other.parameter_value_separators {
+            return self
+                .parameter_value_separators
+                .cmp(other.parameter_value_separators);
+        }
+
         if self.anchor < other.anchor {
             return Ordering::Less;
         }
@@ -66,7 +99,10 @@ impl<'r, K> Eq for RouteNode<'r, K> {}
 
 impl<'r, K> PartialEq for RouteNode<'r, K> {
     fn eq(&self, other: &Self) -> bool {
-        self.anchor == other.anchor && self.has_parameter == other.has_parameter
+        self.anchor == other.anchor
+            && self.has_parameter == other.has_parameter
+            && self.parameter_value_separators == other.parameter_value_separators
+            && self.priority == other.priority
     }
 }
 
@@ -75,8 +111,11 @@ impl<'r, K> Default for RouteNode<'r, K> {
         Self {
             route_key: None,
             route_parameter_names: Default::default(),
+            route_index: Default::default(),
             anchor: Default::default(),
             has_parameter: Default::default(),
+            parameter_value_separators: Default::default(),
+            priority: Default::default(),
             children: Default::default(),
             parent: Default::default(),
         }
@@ -90,8 +129,16 @@ mod tests {
     use std::iter::FromIterator;
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn route_ordering() {
         let nodes = vec![
+            RouteNode {
+                route_key: None,
+                has_parameter: true,
+                priority: 1,
+                anchor: "",
+                ..Default::default()
+            },
             RouteNode {
                 route_key: None,
                 has_parameter: false,
@@ -104,6 +151,13 @@ mod tests {
                 anchor: "xx",
                 ..Default::default()
             },
+            RouteNode {
+                route_key: None,
+                has_parameter: true,
+                parameter_value_separators: "/",
+                anchor: "aa",
+                ..Default::default()
+            },
             RouteNode {
                 route_key: None,
                 has_parameter: true,
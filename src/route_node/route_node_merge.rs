@@ -1,15 +1,17 @@
 use super::*;
 use std::{cell::RefCell, rc::Rc};
 
-pub fn route_node_merge<'r, K>(
+#[allow(clippy::too_many_arguments, clippy::needless_return)]
+pub fn route_node_merge<'r, K: Clone + Eq>(
     parent_node_rc: RouteNodeRc<'r, K>,
     child_node_rc: Option<RouteNodeRc<'r, K>>,
     anchor: &'r str,
     has_parameter: bool,
+    parameter_value_separators: &'r str,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
     common_prefix_length: usize,
-) -> RouteNodeRc<'r, K> {
+) -> Result<RouteNodeRc<'r, K>, K> {
     if let Some(child_node_rc) = child_node_rc {
         let common_prefix = &anchor[..common_prefix_length];
         let child_anchor = child_node_rc.borrow().anchor;
@@ -22,39 +24,43 @@ pub fn route_node_merge<'r, K>(
                 child_node_rc,
                 anchor,
                 has_parameter,
+                parameter_value_separators,
                 route_key,
                 route_parameter_names.clone(),
                 common_prefix_length,
             );
         } else if anchor == common_prefix {
-            return route_node_merge_add_to_new(
+            return Ok(route_node_merge_add_to_new(
                 parent_node_rc,
                 child_node_rc,
                 anchor,
                 has_parameter,
+                parameter_value_separators,
                 route_key,
                 route_parameter_names.clone(),
                 common_prefix_length,
-            );
+            ));
         } else {
-            return route_node_merge_intermediate(
+            return Ok(route_node_merge_intermediate(
                 parent_node_rc,
                 child_node_rc,
                 anchor,
                 has_parameter,
+                parameter_value_separators,
                 route_key,
                 route_parameter_names.clone(),
                 common_prefix_length,
-            );
+            ));
         }
     } else {
-        return route_node_merge_new(
+        return Ok(route_node_merge_new(
             parent_node_rc,
             anchor,
             has_parameter,
+            parameter_value_separators,
             route_key,
             route_parameter_names.clone(),
-        );
+        ));
     }
 }
 
@@ -62,12 +68,14 @@ fn route_node_merge_new<'r, K>(
     parent_node_rc: RouteNodeRc<'r, K>,
     anchor: &'r str,
     has_parameter: bool,
+    parameter_value_separators: &'r str,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
 ) -> RouteNodeRc<'r, K> {
     let new_node = RouteNode::<K> {
         anchor,
         has_parameter,
+        parameter_value_separators,
         route_key,
         route_parameter_names,
         parent: Some(Rc::downgrade(&parent_node_rc)),
@@ -81,30 +89,36 @@ fn route_node_merge_new<'r, K>(
     node_new_rc
 }
 
-fn route_node_merge_join<'r, K>(
+// fails with the key of the route of the child if both the child and the new node have a
+// different route, the routes would match the same paths
+fn route_node_merge_join<'r, K: Clone + Eq>(
     child_node_rc: RouteNodeRc<'r, K>,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
-) -> RouteNodeRc<'r, K> {
+) -> Result<RouteNodeRc<'r, K>, K> {
     let mut child_node = child_node_rc.borrow_mut();
 
-    if child_node.route_key.is_some() && route_key.is_some() {
-        panic!("ambiguous route")
+    if let (Some(child_route_key), Some(route_key)) = (&child_node.route_key, &route_key) {
+        if child_route_key != route_key {
+            return Err(child_route_key.clone());
+        }
     }
 
-    if child_node.route_key.is_none() {
+    if route_key.is_some() {
         child_node.route_key = route_key;
         child_node.route_parameter_names = route_parameter_names;
     }
 
-    child_node_rc.clone()
+    Ok(child_node_rc.clone())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn route_node_merge_intermediate<'r, K>(
     parent_node_rc: RouteNodeRc<'r, K>,
     child_node_rc: RouteNodeRc<'r, K>,
     anchor: &'r str,
     has_parameter: bool,
+    parameter_value_separators: &'r str,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
     common_prefix_length: usize,
@@ -112,6 +126,7 @@ fn route_node_merge_intermediate<'r, K>(
     let new_node = RouteNode {
         anchor,
         has_parameter,
+        parameter_value_separators,
         route_key,
         route_parameter_names,
         ..Default::default()
@@ -132,6 +147,8 @@ fn route_node_merge_intermediate<'r, K>(
         let mut intermediate_node = RouteNode {
             anchor: &child_node.anchor[..common_prefix_length],
             has_parameter: child_node.has_parameter,
+            parameter_value_separators: child_node.parameter_value_separators,
+            priority: child_node.priority,
             parent: Some(Rc::downgrade(&parent_node_rc)),
             ..Default::default()
         };
@@ -156,54 +173,69 @@ fn route_node_merge_intermediate<'r, K>(
         new_node.parent = Some(Rc::downgrade(&intermediate_node_rc));
         new_node.anchor = &new_node.anchor[common_prefix_length..];
         new_node.has_parameter = false;
+        new_node.parameter_value_separators = "";
 
         child_node.parent = Some(Rc::downgrade(&intermediate_node_rc));
         child_node.anchor = &child_node.anchor[common_prefix_length..];
         child_node.has_parameter = false;
+        child_node.parameter_value_separators = "";
     }
 
     // return rc to the new node
     new_node_rc.clone()
 }
 
-fn route_node_merge_add_to_child<'r, K>(
+#[allow(clippy::too_many_arguments, clippy::needless_return)]
+fn route_node_merge_add_to_child<'r, K: Clone + Eq>(
     _parent_node_rc: RouteNodeRc<'r, K>,
     child_node_rc: RouteNodeRc<'r, K>,
     anchor: &'r str,
     _has_parameter: bool,
+    _parameter_value_separators: &'r str,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
     common_prefix_length: usize,
-) -> RouteNodeRc<'r, K> {
+) -> Result<RouteNodeRc<'r, K>, K> {
     let anchor = &anchor[common_prefix_length..];
     let has_parameter = false;
+    let parameter_value_separators = "";
 
-    let (common_prefix_length2, child_node_rc2) =
-        route_node_find_similar_child(&child_node_rc.borrow(), anchor, has_parameter);
+    let (common_prefix_length2, child_node_rc2) = route_node_find_similar_child(
+        &child_node_rc.borrow(),
+        anchor,
+        has_parameter,
+        parameter_value_separators,
+    );
 
     return route_node_merge(
         child_node_rc.clone(),
         child_node_rc2,
         anchor,
         has_parameter,
+        parameter_value_separators,
         route_key,
         route_parameter_names,
         common_prefix_length2,
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn route_node_merge_add_to_new<'r, K>(
     parent_node_rc: RouteNodeRc<'r, K>,
     child_node_rc: RouteNodeRc<'r, K>,
     anchor: &'r str,
     has_parameter: bool,
+    parameter_value_separators: &'r str,
     route_key: Option<K>,
     route_parameter_names: Vec<&'r str>,
     common_prefix_length: usize,
 ) -> RouteNodeRc<'r, K> {
+    // the child becomes a child of the new node, so the new node has at least it's priority
     let new_node = RouteNode {
         anchor,
         has_parameter,
+        parameter_value_separators,
+        priority: child_node_rc.borrow().priority,
         route_key,
         route_parameter_names,
         ..Default::default()
@@ -222,6 +254,7 @@ fn route_node_merge_add_to_new<'r, K>(
     let mut child_node = child_node_rc.borrow_mut();
     child_node.anchor = &child_node.anchor[common_prefix_length..];
     child_node.has_parameter = false;
+    child_node.parameter_value_separators = "";
     child_node.parent = Some(Rc::downgrade(&new_node_rc));
 
     new_node_rc.clone()
@@ -0,0 +1,180 @@
+use super::*;
+use std::fmt::{Debug, Write};
+
+pub fn route_node_print_tree<K: Debug>(node_rc: RouteNodeRc<'_, K>) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "{}", route_node_label(&node_rc.borrow())).unwrap();
+    route_node_print_tree_children(&mut output, node_rc, "");
+
+    output
+}
+
+fn route_node_print_tree_children<K: Debug>(
+    output: &mut String,
+    node_rc: RouteNodeRc<'_, K>,
+    prefix: &str,
+) {
+    let node = node_rc.borrow();
+
+    // children are printed in the order they are tried when parsing, the number is that order
+    for (index, child_rc) in node.children.iter().enumerate() {
+        let is_last = index == node.children.len() - 1;
+        let (connector, child_prefix) = if is_last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+
+        writeln!(
+            output,
+            "{}{}{}. {}",
+            prefix,
+            connector,
+            index + 1,
+            route_node_label(&child_rc.borrow())
+        )
+        .unwrap();
+
+        route_node_print_tree_children(
+            output,
+            child_rc.clone(),
+            &format!("{}{}", prefix, child_prefix),
+        );
+    }
+}
+
+pub fn route_node_print_dot<K: Debug>(node_rc: RouteNodeRc<'_, K>) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "digraph router {{").unwrap();
+    writeln!(output, "    node [shape=box, fontname=monospace];").unwrap();
+    route_node_print_dot_node(&mut output, node_rc, &mut 0);
+    writeln!(output, "}}").unwrap();
+
+    output
+}
+
+fn route_node_print_dot_node<K: Debug>(
+    output: &mut String,
+    node_rc: RouteNodeRc<'_, K>,
+    node_count: &mut usize,
+) -> usize {
+    let node = node_rc.borrow();
+
+    let node_id = *node_count;
+    *node_count += 1;
+
+    // nodes that represent a route get a double border
+    let peripheries = if node.route_key.is_some() { 2 } else { 1 };
+    writeln!(
+        output,
+        "    n{} [label=\"{}\", peripheries={}];",
+        node_id,
+        escape_dot(&route_node_label(&node)),
+        peripheries
+    )
+    .unwrap();
+
+    for (index, child_rc) in node.children.iter().enumerate() {
+        let child_node_id = route_node_print_dot_node(output, child_rc.clone(), node_count);
+        writeln!(
+            output,
+            "    n{} -> n{} [label=\"{}\"];",
+            node_id,
+            child_node_id,
+            index + 1
+        )
+        .unwrap();
+    }
+
+    node_id
+}
+
+fn route_node_label<K: Debug>(node: &RouteNode<'_, K>) -> String {
+    // parameters are printed as an empty placeholder, the anchor is quoted so whitespace is visible
+    let mut label = String::new();
+    if node.has_parameter {
+        write!(label, "{{}}").unwrap();
+    }
+    write!(label, "{:?}", node.anchor).unwrap();
+    if let Some(route_key) = &node.route_key {
+        write!(label, " => {:?}", route_key).unwrap();
+        if !node.route_parameter_names.is_empty() {
+            write!(label, " ({})", node.route_parameter_names.join(", ")).unwrap();
+        }
+    }
+
+    label
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::route_node_rc::route_node_insert;
+    use super::*;
+    use crate::template::template_pairs::parse_template_pairs;
+    use crate::template::TEMPLATE_PLACEHOLDER_REGEX;
+
+    fn create_root_node_rc() -> RouteNodeRc<'static, &'static str> {
+        let root_node_rc = RouteNodeRc::default();
+        for template in ["/a", "/b/{x}", "/b/{y}/c", "/b/{z}/d"] {
+            let template_pairs: Vec<_> =
+                parse_template_pairs(template, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+            route_node_insert(root_node_rc.clone(), template, &template_pairs, |_| "", 0).unwrap();
+        }
+
+        root_node_rc
+    }
+
+    #[test]
+    fn route_node_print_tree_test() {
+        assert_eq!(
+            route_node_print_tree(create_root_node_rc()),
+            [
+                r#""""#,
+                r#"└─ 1. "/""#,
+                r#"   ├─ 1. "b/""#,
+                r#"   │  ├─ 1. {}"/""#,
+                r#"   │  │  ├─ 1. "c" => "/b/{y}/c" (y)"#,
+                r#"   │  │  └─ 2. "d" => "/b/{z}/d" (z)"#,
+                r#"   │  └─ 2. {}"" => "/b/{x}" (x)"#,
+                r#"   └─ 2. "a" => "/a""#,
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn route_node_print_dot_test() {
+        assert_eq!(
+            route_node_print_dot(create_root_node_rc()),
+            [
+                r#"digraph router {"#,
+                r#"    node [shape=box, fontname=monospace];"#,
+                r#"    n0 [label="\"\"", peripheries=1];"#,
+                r#"    n1 [label="\"/\"", peripheries=1];"#,
+                r#"    n2 [label="\"b/\"", peripheries=1];"#,
+                r#"    n3 [label="{}\"/\"", peripheries=1];"#,
+                r#"    n4 [label="\"c\" => \"/b/{y}/c\" (y)", peripheries=2];"#,
+                r#"    n3 -> n4 [label="1"];"#,
+                r#"    n5 [label="\"d\" => \"/b/{z}/d\" (z)", peripheries=2];"#,
+                r#"    n3 -> n5 [label="2"];"#,
+                r#"    n2 -> n3 [label="1"];"#,
+                r#"    n6 [label="{}\"\" => \"/b/{x}\" (x)", peripheries=2];"#,
+                r#"    n2 -> n6 [label="2"];"#,
+                r#"    n1 -> n2 [label="1"];"#,
+                r#"    n7 [label="\"a\" => \"/a\"", peripheries=2];"#,
+                r#"    n1 -> n7 [label="2"];"#,
+                r#"    n0 -> n1 [label="1"];"#,
+                r#"}"#,
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -1,86 +1,427 @@
 use super::route_node_merge::*;
 use super::*;
-use crate::template::template_pairs::parse_template_pairs;
-use regex::Regex;
+use crate::string_utility::{
+    ends_with_ignore_ascii_case, find_ignore_ascii_case, floor_char_boundary,
+    starts_with_ignore_ascii_case,
+};
+use crate::trace::{ParseTrace, ParseTraceOutcome};
 use std::borrow::Cow;
 use std::cmp::min;
+use std::collections::HashMap;
 
-pub fn route_node_parse<'r, 'f, K: Copy>(
+#[derive(Debug, Clone, Copy)]
+pub struct RouteNodeParseOptions {
+    // maximum length of a parameter value, limits the window where we look for the anchor
+    pub maximum_parameter_value_length: usize,
+    // compare anchors while ignoring ascii case
+    pub case_insensitive: bool,
+    // also look for a route that would match if a trailing slash was added to or removed from
+    // the path
+    pub trailing_slash: bool,
+    // try every occurrence of the anchor in the window instead of only the first one
+    pub backtrack: bool,
+    // when backtracking, try the last occurrence first so parameter values are as long as possible
+    pub prefer_longest: bool,
+}
+
+// the index of the matched route, the parameter names and the parameter values. The last element
+// is true if the route only matches when a trailing slash is added to or removed from the path,
+// an exact match is always preferred over such a match
+pub type RouteNodeParseResult<'r, 'f> = (Option<usize>, Vec<&'r str>, Vec<&'f str>, bool);
+
+// results that are not an exact match, by node and the length of the path that is left. When
+// backtracking the same node is tried with the same path again and again, every occurrence of
+// an anchor is combined with every occurrence of the next one. Without remembering the results
+// the number of tries grows exponentially with the number of parameters in a route.
+type RouteNodeParseMemo<'r, 'f> = HashMap<(usize, usize), RouteNodeParseResult<'r, 'f>>;
+
+pub fn route_node_parse<'r, 'f, K: Clone>(
+    node_rc: RouteNodeRc<'r, K>,
+    path: &'f str,
+    options: &RouteNodeParseOptions,
+    trace: Option<&mut ParseTrace<'r, 'f, K>>,
+) -> RouteNodeParseResult<'r, 'f> {
+    let mut memo = Default::default();
+    route_node_parse_memoized(node_rc, path, options, &mut memo, trace)
+}
+
+fn route_node_parse_memoized<'r, 'f, K: Clone>(
     node_rc: RouteNodeRc<'r, K>,
     path: &'f str,
-    maximum_parameter_value_length: usize,
-) -> (Option<K>, Vec<&'r str>, Vec<&'f str>) {
-    let mut path = path;
-    let mut parameter_values: Vec<&str> = Default::default();
+    options: &RouteNodeParseOptions,
+    memo: &mut RouteNodeParseMemo<'r, 'f>,
+    trace: Option<&mut ParseTrace<'r, 'f, K>>,
+) -> RouteNodeParseResult<'r, 'f> {
+    // without backtracking every node is tried at most once
+    if !options.backtrack {
+        return route_node_parse_node(node_rc, path, options, memo, trace);
+    }
 
+    // the path is always a suffix of the path we started with, so it's length identifies it
+    let memo_key = (Rc::as_ptr(&node_rc) as usize, path.len());
+    if let Some(result) = memo.get(&memo_key) {
+        if let Some(trace) = trace {
+            let node = node_rc.borrow();
+            trace.record(
+                node.anchor,
+                node.has_parameter,
+                path,
+                ParseTraceOutcome::AlreadyTried,
+            );
+        }
+        return result.clone();
+    }
+
+    let result = route_node_parse_node(node_rc, path, options, memo, trace);
+
+    // an exact match ends the search, so there is no need to remember it
+    if !matches!(result, (Some(_), _, _, false)) {
+        memo.insert(memo_key, result.clone());
+    }
+
+    result
+}
+
+fn route_node_parse_node<'r, 'f, K: Clone>(
+    node_rc: RouteNodeRc<'r, K>,
+    path: &'f str,
+    options: &RouteNodeParseOptions,
+    memo: &mut RouteNodeParseMemo<'r, 'f>,
+    mut trace: Option<&mut ParseTrace<'r, 'f, K>>,
+) -> RouteNodeParseResult<'r, 'f> {
     let node = node_rc.borrow();
+    let node_path = path;
+
+    // the anchor without it's trailing slash, if the node has a route and the path ends with
+    // this then the route would match if we added a slash to the path
+    let slashless_anchor = node
+        .anchor
+        .strip_suffix('/')
+        .filter(|_| options.trailing_slash && node.route_key.is_some());
 
     if node.has_parameter {
         // we are matching a parameter value! If the path's length is 0, there is no match, because a parameter value should have at least length 1
         if path.is_empty() {
+            if let Some(trace) = trace {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    node_path,
+                    ParseTraceOutcome::EmptyParameterValue,
+                );
+            }
             return Default::default();
         }
 
-        // look for the anchor in the path. If the anchor is empty, match the remainder of the path
-        let index = if node.anchor.is_empty() {
-            Some(path.len())
+        // look for the anchor in the path. If the anchor is empty, match the remainder of the path.
+        // The window is limited in bytes, but never ends in the middle of a char.
+        // a parameter value may not contain a separator, so the anchor has to start at or before
+        // the first separator
+        let separator_index = if node.parameter_value_separators.is_empty() {
+            None
+        } else {
+            path.find(|character| node.parameter_value_separators.contains(character))
+        };
+        let window = if node.anchor.is_empty() {
+            path
+        } else {
+            let window_length = min(
+                options.maximum_parameter_value_length,
+                separator_index.unwrap_or(usize::MAX),
+            ) + node.anchor.len();
+            &path[..floor_char_boundary(path, window_length)]
+        };
+        let mut indices = if node.anchor.is_empty() {
+            if separator_index.is_none() {
+                vec![path.len()]
+            } else {
+                Vec::new()
+            }
         } else {
-            path[..min(
-                maximum_parameter_value_length + node.anchor.len(),
-                path.len(),
-            )]
-                .find(node.anchor)
+            find_anchor_indices(window, node.anchor, options)
         };
 
-        if let Some(index) = index {
+        if indices.is_empty() {
+            if let Some(slashless_anchor) = slashless_anchor {
+                let index = path.len() - min(slashless_anchor.len(), path.len());
+                if index <= options.maximum_parameter_value_length
+                    && separator_index.unwrap_or(usize::MAX) >= index
+                    && ends_with(path, slashless_anchor, options.case_insensitive)
+                {
+                    if let Some(trace) = trace {
+                        trace.record(
+                            node.anchor,
+                            node.has_parameter,
+                            node_path,
+                            ParseTraceOutcome::TrailingSlashMismatch(
+                                node.route_key.clone().unwrap(),
+                            ),
+                        );
+                    }
+                    return (
+                        Some(node.route_index),
+                        node.route_parameter_names.clone(),
+                        vec![&path[..index]],
+                        true,
+                    );
+                }
+            }
+
+            if let Some(trace) = trace {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    node_path,
+                    ParseTraceOutcome::AnchorNotFound {
+                        window: Cow::Borrowed(window),
+                    },
+                );
+            }
+            return Default::default();
+        }
+
+        if options.prefer_longest {
+            indices.reverse();
+        }
+
+        // the first match that is only a match with a trailing slash toggled, we only return this
+        // if no other occurrence of the anchor results in an exact match
+        let mut trailing_slash_result = None;
+
+        for index in indices {
             let value = &path[..index];
 
-            // remove the matches part from the path
-            path = &path[index + node.anchor.len()..];
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    node_path,
+                    ParseTraceOutcome::ParameterMatched {
+                        window: Cow::Borrowed(window),
+                        value: Cow::Borrowed(value),
+                    },
+                );
+            }
 
-            parameter_values.push(value);
-        } else {
-            return Default::default();
+            // remove the matched part from the path
+            let result = route_node_parse_children(
+                &node,
+                &path[index + node.anchor.len()..],
+                vec![value],
+                options,
+                memo,
+                trace.as_deref_mut(),
+            );
+
+            match result {
+                (Some(_), _, _, false) => return result,
+                (Some(_), _, _, true) if trailing_slash_result.is_none() => {
+                    trailing_slash_result = Some(result)
+                }
+                _ => {}
+            }
         }
+
+        trailing_slash_result.unwrap_or_default()
     } else {
         // if this node does not represent a parameter we expect the path to start with the `anchor`
-        if !path.starts_with(node.anchor) {
+        let is_match = if options.case_insensitive {
+            starts_with_ignore_ascii_case(path, node.anchor)
+        } else {
+            path.starts_with(node.anchor)
+        };
+        if !is_match {
+            if let Some(slashless_anchor) = slashless_anchor {
+                if path.len() == slashless_anchor.len()
+                    && ends_with(path, slashless_anchor, options.case_insensitive)
+                {
+                    if let Some(trace) = trace {
+                        trace.record(
+                            node.anchor,
+                            node.has_parameter,
+                            node_path,
+                            ParseTraceOutcome::TrailingSlashMismatch(
+                                node.route_key.clone().unwrap(),
+                            ),
+                        );
+                    }
+                    return (
+                        Some(node.route_index),
+                        node.route_parameter_names.clone(),
+                        Default::default(),
+                        true,
+                    );
+                }
+            }
+
             // this node does not match the path
+            if let Some(trace) = trace {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    node_path,
+                    ParseTraceOutcome::AnchorMismatch,
+                );
+            }
             return Default::default();
         }
 
         // we successfully matches the node to the path, now remove the matched part from the path
-        path = &path[node.anchor.len()..];
+        let path = &path[node.anchor.len()..];
+
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.record(
+                node.anchor,
+                node.has_parameter,
+                node_path,
+                ParseTraceOutcome::AnchorMatched,
+            );
+        }
+
+        route_node_parse_children(&node, path, Vec::new(), options, memo, trace)
     }
+}
+
+// match the children of a node against the rest of the path, or the node itself if there is no
+// path left. The parameter values are the values that were matched by this node.
+fn route_node_parse_children<'r, 'f, K: Clone>(
+    node: &RouteNode<'r, K>,
+    path: &'f str,
+    parameter_values: Vec<&'f str>,
+    options: &RouteNodeParseOptions,
+    memo: &mut RouteNodeParseMemo<'r, 'f>,
+    mut trace: Option<&mut ParseTrace<'r, 'f, K>>,
+) -> RouteNodeParseResult<'r, 'f> {
+    // the first route that matches when a trailing slash is added or removed, we only return
+    // this if there is no exact match
+    let mut trailing_slash_match = None;
 
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.enter();
+    }
     for child_rc in &node.children {
-        if let (Some(child_route_name), child_route_parameter_names, mut child_parameters_values) =
-            route_node_parse(child_rc.clone(), path, maximum_parameter_value_length)
+        if let (
+            Some(child_route),
+            child_route_parameter_names,
+            mut child_parameters_values,
+            child_trailing_slash,
+        ) =
+            route_node_parse_memoized(child_rc.clone(), path, options, memo, trace.as_deref_mut())
         {
             let mut parameter_values = parameter_values.clone();
             parameter_values.append(&mut child_parameters_values);
+
+            if child_trailing_slash {
+                if trailing_slash_match.is_none() {
+                    trailing_slash_match =
+                        Some((child_route, child_route_parameter_names, parameter_values));
+                }
+                continue;
+            }
+
+            if let Some(trace) = trace {
+                trace.leave();
+            }
+
             return (
-                Some(child_route_name),
+                Some(child_route),
                 child_route_parameter_names,
                 parameter_values,
+                false,
             );
         }
     }
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.leave();
+    }
 
     // if the node had a route name and there is no path left to match against then we found a route
     if path.is_empty() {
-        if let Some(route_key) = node.route_key {
+        if let Some(route_key) = &node.route_key {
+            if let Some(trace) = trace {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    path,
+                    ParseTraceOutcome::RouteMatched(route_key.clone()),
+                );
+            }
             return (
-                Some(route_key),
+                Some(node.route_index),
                 node.route_parameter_names.clone(),
                 parameter_values,
+                false,
             );
         }
     }
 
+    // if the only thing left is a slash, then this route would match without it
+    if options.trailing_slash && trailing_slash_match.is_none() && path == "/" {
+        if let Some(route_key) = &node.route_key {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.record(
+                    node.anchor,
+                    node.has_parameter,
+                    path,
+                    ParseTraceOutcome::TrailingSlashMismatch(route_key.clone()),
+                );
+            }
+            trailing_slash_match = Some((
+                node.route_index,
+                node.route_parameter_names.clone(),
+                parameter_values,
+            ));
+        }
+    }
+
+    if let Some((route, route_parameter_names, parameter_values)) = trailing_slash_match {
+        return (Some(route), route_parameter_names, parameter_values, true);
+    }
+
+    if let Some(trace) = trace {
+        let outcome = if path.is_empty() {
+            ParseTraceOutcome::NoRoute
+        } else {
+            ParseTraceOutcome::PathRemaining(Cow::Borrowed(path))
+        };
+        trace.record(node.anchor, node.has_parameter, path, outcome);
+    }
+
     Default::default()
 }
 
+// the indices of the occurrences of the anchor in the window, only the first one if we do not
+// backtrack. Occurrences may overlap.
+fn find_anchor_indices(window: &str, anchor: &str, options: &RouteNodeParseOptions) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut offset = 0;
+
+    while let Some(index) = if options.case_insensitive {
+        find_ignore_ascii_case(&window[offset..], anchor)
+    } else {
+        window[offset..].find(anchor)
+    } {
+        let index = offset + index;
+        indices.push(index);
+
+        if !options.backtrack {
+            break;
+        }
+
+        offset = index + window[index..].chars().next().unwrap().len_utf8();
+    }
+
+    indices
+}
+
+fn ends_with(value: &str, suffix: &str, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        ends_with_ignore_ascii_case(value, suffix)
+    } else {
+        value.ends_with(suffix)
+    }
+}
+
 pub fn route_node_stringify<'r, 'f, K>(
     node_rc: RouteNodeRc<'r, K>,
     parameter_values: Vec<Cow<'f, str>>,
@@ -113,13 +454,15 @@ where
         .unwrap()
 }
 
-pub fn route_node_insert<'r, K: Copy>(
+// fails with the key of the existing route if there already is a route that matches the same
+// paths, the trie is not changed in that case
+pub fn route_node_insert<'r, K: Clone + Eq>(
     root_node_rc: RouteNodeRc<'r, K>,
     route_key: K,
-    template: &'r str,
-    parameter_placeholder_re: &'r Regex,
-) -> RouteNodeRc<'r, K> {
-    let template_pairs: Vec<_> = parse_template_pairs(template, parameter_placeholder_re).collect();
+    template_pairs: &[(&'r str, Option<&'r str>)],
+    parameter_value_separators: impl Fn(&'r str) -> &'r str,
+    priority: u32,
+) -> Result<RouteNodeRc<'r, K>, K> {
     let route_parameter_names: Vec<_> = template_pairs
         .iter()
         .cloned()
@@ -130,36 +473,194 @@ pub fn route_node_insert<'r, K: Copy>(
     for index in 0..template_pairs.len() {
         let (anchor, parameter) = template_pairs[index];
         let has_parameter = parameter.is_some();
+        let parameter_value_separators = parameter
+            .map(&parameter_value_separators)
+            .unwrap_or_default();
         let route_key = if index == template_pairs.len() - 1 {
-            Some(route_key)
+            Some(route_key.clone())
         } else {
             None
         };
 
-        let (common_prefix_length, child_node_rc) =
-            route_node_find_similar_child(&node_current_rc.borrow(), anchor, has_parameter);
+        let (common_prefix_length, child_node_rc) = route_node_find_similar_child(
+            &node_current_rc.borrow(),
+            anchor,
+            has_parameter,
+            parameter_value_separators,
+        );
 
         node_current_rc = route_node_merge(
             node_current_rc,
             child_node_rc,
             anchor,
             has_parameter,
+            parameter_value_separators,
             route_key,
             route_parameter_names.clone(),
             common_prefix_length,
-        );
+        )?;
+    }
+
+    route_node_raise_priority(node_current_rc.clone(), priority);
+
+    Ok(node_current_rc)
+}
+
+// the anchor, the parameter name and the separators of every node from the root to a leaf
+// node, these are the template pairs of the route as they are in the trie
+pub fn route_node_pairs<'r, K>(
+    leaf_node_rc: RouteNodeRc<'r, K>,
+) -> Vec<(&'r str, Option<&'r str>, &'r str)> {
+    let mut route_parameter_names = leaf_node_rc.borrow().route_parameter_names.clone();
+
+    let mut node_pairs = Vec::new();
+    let mut current_node_rc = Some(leaf_node_rc);
+    while let Some(node_rc) = current_node_rc {
+        let node = node_rc.borrow();
+        current_node_rc = node
+            .parent
+            .as_ref()
+            .map(|parent_node_weak| parent_node_weak.upgrade().unwrap());
+
+        // the root node is not part of the route
+        if current_node_rc.is_none() {
+            break;
+        }
+
+        let parameter = if node.has_parameter {
+            route_parameter_names.pop()
+        } else {
+            None
+        };
+        node_pairs.push((node.anchor, parameter, node.parameter_value_separators));
+    }
+    node_pairs.reverse();
+
+    node_pairs
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteNodeGraftError<K> {
+    // there already is a route that matches the same paths, with this key
+    AmbiguousRoute(K),
+    // the prefix ends with a parameter and the route starts with one, like `/p/{x}` and `{y}/a`
+    AdjacentParameters,
+}
+
+// insert the route of a leaf node of another trie under a prefix. The path from the root of the
+// other trie to the leaf is turned back into template pairs, so the nodes keep their separators
+// and their priority. The parameters of the prefix come before the parameters of the route. The
+// trie is not changed if the route cannot be grafted.
+pub fn route_node_graft<'r, K: Clone + Eq, L>(
+    root_node_rc: RouteNodeRc<'r, K>,
+    route_key: K,
+    prefix_template_pairs: &[(&'r str, Option<&'r str>)],
+    prefix_parameter_value_separators: impl Fn(&'r str) -> &'r str,
+    leaf_node_rc: RouteNodeRc<'r, L>,
+) -> Result<RouteNodeRc<'r, K>, RouteNodeGraftError<K>> {
+    let leaf_node = leaf_node_rc.borrow();
+    let node_pairs = route_node_pairs(leaf_node_rc.clone());
+
+    let mut template_pairs = prefix_template_pairs.to_vec();
+    let mut node_pairs = node_pairs.into_iter().peekable();
+    if let Some((anchor, None, _)) = node_pairs.peek().cloned() {
+        // the anchor that comes first in the route is the anchor of the last pair of the prefix,
+        // unless the prefix already ends with a literal
+        match template_pairs.last_mut() {
+            Some(last_pair) if last_pair.0.is_empty() => {
+                last_pair.0 = anchor;
+                node_pairs.next();
+            }
+            _ if anchor.is_empty() => {
+                node_pairs.next();
+            }
+            _ => {}
+        }
+    }
+
+    let mut node_parameter_value_separators = Vec::new();
+    for (anchor, parameter, parameter_value_separators) in node_pairs {
+        if let Some(parameter) = parameter {
+            node_parameter_value_separators.push((parameter, parameter_value_separators));
+        }
+        template_pairs.push((anchor, parameter));
+    }
+
+    // a parameter without an anchor matches the rest of the path, so it can only be the last one
+    if template_pairs[..template_pairs.len() - 1]
+        .iter()
+        .any(|(anchor, parameter)| anchor.is_empty() && parameter.is_some())
+    {
+        return Err(RouteNodeGraftError::AdjacentParameters);
+    }
+
+    route_node_insert(
+        root_node_rc,
+        route_key,
+        &template_pairs,
+        |parameter_name| {
+            node_parameter_value_separators
+                .iter()
+                .find(|(name, _)| *name == parameter_name)
+                .map(|(_, parameter_value_separators)| *parameter_value_separators)
+                .unwrap_or_else(|| prefix_parameter_value_separators(parameter_name))
+        },
+        leaf_node.priority,
+    )
+    .map_err(RouteNodeGraftError::AmbiguousRoute)
+}
+
+// a deep copy of a trie, routes can be inserted into the copy without changing the original
+pub fn route_node_clone<'r, K: Clone>(node_rc: &RouteNodeRc<'r, K>) -> RouteNodeRc<'r, K> {
+    let node = node_rc.borrow();
+    let node_clone_rc = Rc::new(RefCell::new(RouteNode {
+        route_key: node.route_key.clone(),
+        route_parameter_names: node.route_parameter_names.clone(),
+        route_index: node.route_index,
+        anchor: node.anchor,
+        has_parameter: node.has_parameter,
+        parameter_value_separators: node.parameter_value_separators,
+        priority: node.priority,
+        children: Default::default(),
+        parent: None,
+    }));
+
+    for child_node_rc in node.children.iter() {
+        let child_node_clone_rc = route_node_clone(child_node_rc);
+        child_node_clone_rc.borrow_mut().parent = Some(Rc::downgrade(&node_clone_rc));
+        node_clone_rc
+            .borrow_mut()
+            .children
+            .insert(child_node_clone_rc);
+    }
+
+    node_clone_rc
+}
+
+// every node of a trie that is the leaf node of a route
+pub fn route_node_leaves<'r, K>(node_rc: &RouteNodeRc<'r, K>) -> Vec<RouteNodeRc<'r, K>> {
+    let mut leaf_nodes_rc = Vec::new();
+    if node_rc.borrow().route_key.is_some() {
+        leaf_nodes_rc.push(node_rc.clone());
+    }
+
+    for child_node_rc in node_rc.borrow().children.iter() {
+        leaf_nodes_rc.append(&mut route_node_leaves(child_node_rc));
     }
 
-    node_current_rc
+    leaf_nodes_rc
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::route_node::route_node_print::route_node_print_tree;
+    use crate::template::template_pairs::parse_template_pairs;
     use crate::template::TEMPLATE_PLACEHOLDER_REGEX;
     use itertools::Itertools;
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn route_node_permutations() {
         let route_configs = vec!["/a", "/b/{x}", "/b/{x}/", "/b/{x}/c", "/b/{y}/d"];
 
@@ -169,12 +670,10 @@ mod tests {
             let node_root_rc = Rc::new(RefCell::new(RouteNode::default()));
 
             for template in route_configs {
-                route_node_insert(
-                    node_root_rc.clone(),
-                    template,
-                    template,
-                    &TEMPLATE_PLACEHOLDER_REGEX,
-                );
+                let template_pairs: Vec<_> =
+                    parse_template_pairs(template, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+                route_node_insert(node_root_rc.clone(), template, &template_pairs, |_| "", 0)
+                    .unwrap();
             }
 
             {
@@ -189,4 +688,160 @@ mod tests {
             node_root_previous_rc = Some(node_root_rc.clone());
         }
     }
+
+    #[test]
+    fn route_node_graft_test() {
+        let prefix = "/p/{x}";
+        let templates = ["/a", "/b/{y}", "/b/{y}/", "/b/{y}/c", ".{z}/d"];
+        let templates_expected = [
+            "/p/{x}/a",
+            "/p/{x}/b/{y}",
+            "/p/{x}/b/{y}/",
+            "/p/{x}/b/{y}/c",
+            "/p/{x}.{z}/d",
+        ];
+
+        let node_sub_root_rc = Rc::new(RefCell::new(RouteNode::default()));
+        let node_expected_root_rc = Rc::new(RefCell::new(RouteNode::default()));
+        for (template, template_expected) in templates.iter().zip(templates_expected.iter()) {
+            let template_pairs: Vec<_> =
+                parse_template_pairs(template, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+            route_node_insert(
+                node_sub_root_rc.clone(),
+                *template,
+                &template_pairs,
+                |_| "",
+                0,
+            )
+            .unwrap();
+
+            let template_pairs: Vec<_> =
+                parse_template_pairs(template_expected, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+            route_node_insert(
+                node_expected_root_rc.clone(),
+                *template,
+                &template_pairs,
+                |_| "",
+                0,
+            )
+            .unwrap();
+        }
+
+        let prefix_template_pairs: Vec<_> =
+            parse_template_pairs(prefix, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+        let node_root_rc = Rc::new(RefCell::new(RouteNode::default()));
+        for template in templates.iter().cloned() {
+            let leaf_node_rc = find_leaf_node(node_sub_root_rc.clone(), template).unwrap();
+            route_node_graft(
+                node_root_rc.clone(),
+                template,
+                &prefix_template_pairs,
+                |_| "",
+                leaf_node_rc,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            route_node_print_tree(node_root_rc.clone()),
+            route_node_print_tree(node_expected_root_rc)
+        );
+
+        // the parameter of the prefix would be directly followed by the parameter of the route
+        let template_pairs: Vec<_> =
+            parse_template_pairs("{z}/e", &TEMPLATE_PLACEHOLDER_REGEX).collect();
+        route_node_insert(
+            node_sub_root_rc.clone(),
+            "{z}/e",
+            &template_pairs,
+            |_| "",
+            0,
+        )
+        .unwrap();
+        let leaf_node_rc = find_leaf_node(node_sub_root_rc.clone(), "{z}/e").unwrap();
+        let tree = route_node_print_tree(node_root_rc.clone());
+        assert_eq!(
+            route_node_graft(
+                node_root_rc.clone(),
+                "{z}/e",
+                &prefix_template_pairs,
+                |_| "",
+                leaf_node_rc.clone(),
+            ),
+            Err(RouteNodeGraftError::AdjacentParameters)
+        );
+
+        // and a route that matches the same paths as an existing one
+        let leaf_node_rc = find_leaf_node(node_sub_root_rc.clone(), "/b/{y}").unwrap();
+        assert_eq!(
+            route_node_graft(
+                node_root_rc.clone(),
+                "other",
+                &prefix_template_pairs,
+                |_| "",
+                leaf_node_rc
+            ),
+            Err(RouteNodeGraftError::AmbiguousRoute("/b/{y}"))
+        );
+        assert_eq!(route_node_print_tree(node_root_rc), tree);
+    }
+
+    #[test]
+    fn route_node_clone_test() {
+        let node_root_rc = Rc::new(RefCell::new(RouteNode::default()));
+        for template in ["/a", "/b/{x}", "/b/{x}/c"] {
+            let template_pairs: Vec<_> =
+                parse_template_pairs(template, &TEMPLATE_PLACEHOLDER_REGEX).collect();
+            route_node_insert(node_root_rc.clone(), template, &template_pairs, |_| "", 0).unwrap();
+        }
+
+        let node_clone_root_rc = route_node_clone(&node_root_rc);
+        assert_eq!(
+            route_node_print_tree(node_clone_root_rc.clone()),
+            route_node_print_tree(node_root_rc.clone())
+        );
+
+        // the copy has it's own nodes, with parents in the copy
+        let template_pairs: Vec<_> =
+            parse_template_pairs("/d", &TEMPLATE_PLACEHOLDER_REGEX).collect();
+        route_node_insert(node_clone_root_rc.clone(), "/d", &template_pairs, |_| "", 0).unwrap();
+        assert!(find_leaf_node(node_root_rc.clone(), "/d").is_none());
+
+        let leaf_node_rc = find_leaf_node(node_clone_root_rc.clone(), "/b/{x}/c").unwrap();
+        let mut node_rc = leaf_node_rc;
+        loop {
+            let parent_node_rc = node_rc
+                .borrow()
+                .parent
+                .as_ref()
+                .map(|parent| parent.upgrade().unwrap());
+            match parent_node_rc {
+                Some(parent_node_rc) => node_rc = parent_node_rc,
+                None => break,
+            }
+        }
+        assert!(Rc::ptr_eq(&node_rc, &node_clone_root_rc));
+
+        let mut route_keys: Vec<_> = route_node_leaves(&node_clone_root_rc)
+            .iter()
+            .map(|node_rc| node_rc.borrow().route_key.unwrap())
+            .collect();
+        route_keys.sort_unstable();
+        assert_eq!(route_keys, vec!["/a", "/b/{x}", "/b/{x}/c", "/d"]);
+    }
+
+    fn find_leaf_node<'r>(
+        node_rc: RouteNodeRc<'r, &'r str>,
+        route_key: &str,
+    ) -> Option<RouteNodeRc<'r, &'r str>> {
+        if node_rc.borrow().route_key == Some(route_key) {
+            return Some(node_rc);
+        }
+
+        node_rc
+            .borrow()
+            .children
+            .iter()
+            .find_map(|child_node_rc| find_leaf_node(child_node_rc.clone(), route_key))
+    }
 }
@@ -5,17 +5,23 @@ pub fn route_node_find_similar_child<'r, K>(
     parent_node: &RouteNode<'r, K>,
     anchor: &'r str,
     has_parameter: bool,
+    parameter_value_separators: &'r str,
 ) -> (usize, Option<RouteNodeRc<'r, K>>) {
-    let anchor_chars: Vec<_> = anchor.chars().collect();
-
     for child_node_rc in parent_node.children.iter() {
         if child_node_rc.borrow().has_parameter != has_parameter {
             continue;
         }
+        if child_node_rc.borrow().parameter_value_separators != parameter_value_separators {
+            continue;
+        }
 
-        let child_anchor_chars: Vec<_> = child_node_rc.borrow().anchor.chars().collect();
+        // an empty anchor has no prefix in common with anything, but it is the same node as a
+        // child with an empty anchor
+        if anchor.is_empty() && child_node_rc.borrow().anchor.is_empty() {
+            return (0, Some(child_node_rc.clone()));
+        }
 
-        let common_prefix_length = find_common_prefix_length(&anchor_chars, &child_anchor_chars);
+        let common_prefix_length = find_common_prefix_length(anchor, child_node_rc.borrow().anchor);
 
         if common_prefix_length == 0 {
             continue;
@@ -26,3 +32,32 @@ pub fn route_node_find_similar_child<'r, K>(
 
     Default::default()
 }
+
+// raise the priority of a node and it's ancestors, a node is moved in the children of it's parent
+// when it's priority changes so the children stay ordered
+pub fn route_node_raise_priority<K>(node_rc: RouteNodeRc<'_, K>, priority: u32) {
+    let mut current_node_rc = Some(node_rc);
+
+    while let Some(node_rc) = current_node_rc {
+        if node_rc.borrow().priority >= priority {
+            break;
+        }
+
+        let parent_node_rc = node_rc
+            .borrow()
+            .parent
+            .as_ref()
+            .map(|parent_node_weak| parent_node_weak.upgrade().unwrap());
+
+        if let Some(parent_node_rc) = parent_node_rc.as_ref() {
+            let mut parent_node = parent_node_rc.borrow_mut();
+            parent_node.children.remove(&node_rc);
+            node_rc.borrow_mut().priority = priority;
+            parent_node.children.insert(node_rc.clone());
+        } else {
+            node_rc.borrow_mut().priority = priority;
+        }
+
+        current_node_rc = parent_node_rc;
+    }
+}
@@ -1,193 +1,3027 @@
 use crate::{
-    route_node::route_node_rc::{route_node_insert, route_node_parse, route_node_stringify},
+    codec::{decode_component, encode_component, encode_reserved, encode_segments},
+    route_node::route_node_print::{route_node_print_dot, route_node_print_tree},
+    route_node::route_node_rc::{
+        route_node_clone, route_node_graft, route_node_insert, route_node_leaves, route_node_pairs,
+        route_node_parse, route_node_stringify, RouteNodeGraftError, RouteNodeParseOptions,
+    },
     route_node::RouteNodeRc,
+    string_utility::{
+        collapse_duplicate_slashes, contains_encoded_slash, decode_unreserved_percent_escapes,
+        relative_reference,
+    },
+    template::template_pairs::{parse_template_pairs, template_parts_to_pairs},
+    template::template_parts::{
+        parse_template_expression_parts, TemplateParameterEncoding, TemplatePart,
+    },
     template::TEMPLATE_PLACEHOLDER_REGEX,
+    trace::ParseTrace,
 };
 use regex::Regex;
+use std::error::Error;
+use std::fmt::{self, Debug};
 use std::hash::Hash;
+use std::rc::Rc;
 use std::{borrow::Cow, collections::HashMap};
 
+pub type RouteParameters<'r, 'f> = HashMap<&'r str, Cow<'f, str>>;
+
 type ParameterValueEncoder = dyn Fn(&str) -> Cow<str>;
-type ParameterValueDecoder = dyn Fn(&str) -> Cow<str>;
+type ParameterValueDecoder = dyn Fn(&str) -> Result<Cow<str>, Box<dyn Error + Send + Sync>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError<'r> {
+    // the parameter value decoder failed to decode the value of a parameter
+    InvalidParameterEncoding {
+        name: &'r str,
+        value: String,
+    },
+    // the parameter value decoder returned an `InvalidParameterValue`, the value is encoded
+    // correctly but it is not allowed
+    InvalidParameterValue {
+        name: &'r str,
+        value: String,
+        reason: String,
+    },
+}
+
+impl<'r> fmt::Display for ParseError<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidParameterEncoding { name, value } => {
+                write!(f, "invalid encoding of parameter {}: {}", name, value)
+            }
+            ParseError::InvalidParameterValue {
+                name,
+                value,
+                reason,
+            } => write!(
+                f,
+                "invalid value of parameter {}: {} ({})",
+                name, value, reason
+            ),
+        }
+    }
+}
+
+impl<'r> Error for ParseError<'r> {}
+
+// a parameter value decoder returns this error when the value is decoded, but it is not allowed,
+// like a value that does not satisfy the schema of the parameter. Any other error of a decoder
+// means that the value is not encoded correctly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidParameterValue(pub String);
+
+impl fmt::Display for InvalidParameterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for InvalidParameterValue {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringifyError {
+    // there is no route with this key
+    UnknownRoute,
+    // duplicate slashes are collapsed when a path is parsed, so this path would not parse to the
+    // same parameter values
+    DuplicateSlashes { path: String },
+}
+
+impl fmt::Display for StringifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringifyError::UnknownRoute => write!(f, "unknown route"),
+            StringifyError::DuplicateSlashes { path } => {
+                write!(
+                    f,
+                    "path {} has duplicate slashes that would be collapsed",
+                    path
+                )
+            }
+        }
+    }
+}
+
+impl Error for StringifyError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflict<K> {
+    // both routers have a route with this key, but with a different template
+    DuplicateRouteKey {
+        route_key: K,
+        template: String,
+        other_template: String,
+    },
+    // the route of the other router matches the same paths as a route of this router
+    AmbiguousRoute {
+        route_key: K,
+        other_route_key: K,
+        other_template: String,
+    },
+    // the prefix ends with a parameter and the route of the other router starts with one, so the
+    // first parameter would match the rest of the path
+    AdjacentParameters {
+        route_key: K,
+        template: String,
+    },
+}
+
+impl<K: Debug> fmt::Display for MergeConflict<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeConflict::DuplicateRouteKey {
+                route_key,
+                template,
+                other_template,
+            } => write!(
+                f,
+                "route {:?} has template {} and {}",
+                route_key, template, other_template
+            ),
+            MergeConflict::AmbiguousRoute {
+                route_key,
+                other_route_key,
+                other_template,
+            } => write!(
+                f,
+                "route {:?} with template {} matches the same paths as route {:?}",
+                other_route_key, other_template, route_key
+            ),
+            MergeConflict::AdjacentParameters {
+                route_key,
+                template,
+            } => write!(
+                f,
+                "route {:?} with template {} has parameters that are not separated by an anchor",
+                route_key, template
+            ),
+        }
+    }
+}
 
-pub struct Router<'r, K> {
+impl<K: Debug> Error for MergeConflict<K> {}
+
+// the difference between the routes of two routers, every list is sorted by template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouterDiff<K> {
+    // routes that are only in the other router, with their template
+    pub added: Vec<(K, String)>,
+    // routes that are only in this router, with their template
+    pub removed: Vec<(K, String)>,
+    // routes that are in both routers, with the template in this router and in the other
+    pub changed: Vec<(K, String, String)>,
+}
+
+impl<K> RouterDiff<K> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingSlash {
+    // a trailing slash must match the template exactly
+    Strict,
+    // if a path does not match, but it would match with a trailing slash added or removed, then
+    // that route is returned. `parse_route_with_trailing_slash` tells if that happened.
+    Ignore,
+    // like `Ignore`, but also report the canonical path so it can be redirected to
+    RedirectHint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterValueMatch {
+    // a parameter value ends at the first occurrence of the anchor that follows it
+    FirstAnchor,
+    // try every occurrence of the anchor in the window, the shortest value first
+    Shortest,
+    // try every occurrence of the anchor in the window, the longest value first
+    Longest,
+}
+
+// `M` is the type of the metadata that may be stored with every route, like a handler name or
+// the permissions that are required for the route. The metadata is returned when the route
+// matches without an extra lookup.
+pub struct Router<'r, K, M = ()> {
     root_node_rc: RouteNodeRc<'r, K>,
     leaf_nodes_rc: HashMap<K, RouteNodeRc<'r, K>>,
+    // the key and the metadata of every route, by the route index of the leaf node
+    route_keys: Vec<K>,
+    route_metadata: Vec<Option<M>>,
     maximum_parameter_value_length: usize,
+    case_insensitive: bool,
+    collapse_duplicate_slashes: bool,
+    decode_unreserved: bool,
+    #[cfg(feature = "unicode-normalization")]
+    unicode_nfc: bool,
+    reject_encoded_slashes: bool,
+    trailing_slash: TrailingSlash,
+    parameter_value_match: ParameterValueMatch,
+    parameter_value_separators: &'r str,
+    named_parameter_value_separators: HashMap<&'r str, &'r str>,
+    template_operators: bool,
     parameter_placeholder_re: &'r Regex,
     parameter_value_encoder: Box<ParameterValueEncoder>,
     parameter_value_decoder: Box<ParameterValueDecoder>,
+    // if the default encoder was replaced, generated code cannot encode like the router does
+    custom_parameter_value_encoder: bool,
+    // encoders and decoders for a specific route, see `set_parameter_value_encoder` for the
+    // order in which they are tried
+    route_parameter_value_encoders: HashMap<K, Box<ParameterValueEncoder>>,
+    route_parameter_value_decoders: HashMap<K, Box<ParameterValueDecoder>>,
+    // encoders and decoders for every parameter with a specific name
+    named_parameter_value_encoders: HashMap<&'r str, Box<ParameterValueEncoder>>,
+    named_parameter_value_decoders: HashMap<&'r str, Box<ParameterValueDecoder>>,
+    // encoders and decoders for a single parameter of a single route
+    route_named_parameter_value_encoders: HashMap<K, HashMap<&'r str, Box<ParameterValueEncoder>>>,
+    route_named_parameter_value_decoders: HashMap<K, HashMap<&'r str, Box<ParameterValueDecoder>>>,
+    // parameters of templates with rfc 6570 operators that are not encoded as a component
+    template_parameter_encodings: HashMap<K, HashMap<&'r str, TemplateParameterEncoding>>,
+    // other keys of a route, by the alias. The openapi import uses these for the operations that
+    // share a path.
+    route_aliases: HashMap<K, K>,
 }
 
-impl<'r, K: Eq + Hash + Copy> Router<'r, K> {
+impl<'r, K: Eq + Hash + Clone> Router<'r, K> {
     pub fn new() -> Self {
-        fn parameter_encoder(value: &str) -> Cow<str> {
-            urlencoding::encode(value)
+        Self::new_with_metadata()
+    }
+}
+
+impl<'r, K: Eq + Hash + Clone, M> Router<'r, K, M> {
+    // a router that can store metadata of type `M` with every route
+    pub fn new_with_metadata() -> Self {
+        let parameter_value_encoder = Box::new(encode_component);
+        let parameter_value_decoder = Box::new(decode_component);
+
+        Self {
+            root_node_rc: RouteNodeRc::default(),
+            leaf_nodes_rc: HashMap::new(),
+            route_keys: Vec::new(),
+            route_metadata: Vec::new(),
+            maximum_parameter_value_length: 20,
+            case_insensitive: false,
+            collapse_duplicate_slashes: false,
+            decode_unreserved: false,
+            #[cfg(feature = "unicode-normalization")]
+            unicode_nfc: false,
+            reject_encoded_slashes: false,
+            trailing_slash: TrailingSlash::Strict,
+            parameter_value_match: ParameterValueMatch::FirstAnchor,
+            parameter_value_separators: "",
+            named_parameter_value_separators: HashMap::new(),
+            template_operators: false,
+            parameter_placeholder_re: &TEMPLATE_PLACEHOLDER_REGEX,
+            parameter_value_encoder,
+            parameter_value_decoder,
+            custom_parameter_value_encoder: false,
+            route_parameter_value_encoders: HashMap::new(),
+            route_parameter_value_decoders: HashMap::new(),
+            named_parameter_value_encoders: HashMap::new(),
+            named_parameter_value_decoders: HashMap::new(),
+            route_named_parameter_value_encoders: HashMap::new(),
+            route_named_parameter_value_decoders: HashMap::new(),
+            template_parameter_encodings: HashMap::new(),
+            route_aliases: HashMap::new(),
+        }
+    }
+
+    pub fn set_maximum_parameter_value_length(&mut self, value: usize) -> &mut Self {
+        self.maximum_parameter_value_length = value;
+
+        self
+    }
+
+    pub fn set_case_insensitive(&mut self, value: bool) -> &mut Self {
+        self.case_insensitive = value;
+
+        self
+    }
+
+    pub fn set_collapse_duplicate_slashes(&mut self, value: bool) -> &mut Self {
+        self.collapse_duplicate_slashes = value;
+
+        self
+    }
+
+    pub fn set_decode_unreserved(&mut self, value: bool) -> &mut Self {
+        self.decode_unreserved = value;
+
+        self
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_unicode_nfc(&mut self, value: bool) -> &mut Self {
+        self.unicode_nfc = value;
+
+        self
+    }
+
+    pub fn set_reject_encoded_slashes(&mut self, value: bool) -> &mut Self {
+        self.reject_encoded_slashes = value;
+
+        self
+    }
+
+    pub fn set_trailing_slash(&mut self, value: TrailingSlash) -> &mut Self {
+        self.trailing_slash = value;
+
+        self
+    }
+
+    // backtracking is slower, but it matches values that contain the anchor, like `a.b` for
+    // `{name}.json` in `a.b.json` when there is also a `{name}.{extension}` route
+    pub fn set_parameter_value_match(&mut self, value: ParameterValueMatch) -> &mut Self {
+        self.parameter_value_match = value;
+
+        self
+    }
+
+    // characters that a parameter value may not contain, like `/` to keep a value in a single
+    // segment. This is part of the route, so it only applies to templates that are inserted after
+    // this is set.
+    pub fn set_parameter_value_separators(&mut self, value: &'r str) -> &mut Self {
+        self.parameter_value_separators = value;
+
+        self
+    }
+
+    // like `set_parameter_value_separators`, but for every parameter with this name
+    pub fn set_named_parameter_value_separators(
+        &mut self,
+        parameter_name: &'r str,
+        value: &'r str,
+    ) -> &mut Self {
+        self.named_parameter_value_separators
+            .insert(parameter_name, value);
+
+        self
+    }
+
+    // parse rfc 6570 expressions like `{+path}`, `{/segments*}`, `{?query,page}` and `{#frag}`
+    // in templates that are inserted after this is set
+    pub fn set_template_operators(&mut self, value: bool) -> &mut Self {
+        self.template_operators = value;
+
+        self
+    }
+
+    pub fn set_parameter_placeholder_re(&mut self, value: &'r Regex) -> &mut Self {
+        self.parameter_placeholder_re = value;
+
+        self
+    }
+
+    // the encoder of a parameter value is the first one of
+    // - the encoder for the parameter of the route, `set_route_named_parameter_value_encoder`
+    // - the encoder for the route, `set_route_parameter_value_encoder` or
+    //   `insert_route_with_codecs`
+    // - the encoder for the parameter name, `set_named_parameter_value_encoder`
+    // - the encoder of the rfc 6570 expression of the parameter, like `{+name}`
+    // - the default encoder, `set_parameter_value_encoder`
+    // Decoders are found in the same order, an expression does not change how a value is decoded.
+    pub fn set_parameter_value_encoder(&mut self, value: Box<ParameterValueEncoder>) -> &mut Self {
+        self.parameter_value_encoder = value;
+        self.custom_parameter_value_encoder = true;
+
+        self
+    }
+
+    pub fn set_parameter_value_decoder(&mut self, value: Box<ParameterValueDecoder>) -> &mut Self {
+        self.parameter_value_decoder = value;
+
+        self
+    }
+
+    pub fn set_route_parameter_value_encoder(
+        &mut self,
+        route_key: K,
+        value: Box<ParameterValueEncoder>,
+    ) -> &mut Self {
+        self.route_parameter_value_encoders.insert(route_key, value);
+
+        self
+    }
+
+    pub fn set_route_parameter_value_decoder(
+        &mut self,
+        route_key: K,
+        value: Box<ParameterValueDecoder>,
+    ) -> &mut Self {
+        self.route_parameter_value_decoders.insert(route_key, value);
+
+        self
+    }
+
+    pub fn set_named_parameter_value_encoder(
+        &mut self,
+        parameter_name: &'r str,
+        value: Box<ParameterValueEncoder>,
+    ) -> &mut Self {
+        self.named_parameter_value_encoders
+            .insert(parameter_name, value);
+
+        self
+    }
+
+    pub fn set_named_parameter_value_decoder(
+        &mut self,
+        parameter_name: &'r str,
+        value: Box<ParameterValueDecoder>,
+    ) -> &mut Self {
+        self.named_parameter_value_decoders
+            .insert(parameter_name, value);
+
+        self
+    }
+
+    pub fn set_route_named_parameter_value_encoder(
+        &mut self,
+        route_key: K,
+        parameter_name: &'r str,
+        value: Box<ParameterValueEncoder>,
+    ) -> &mut Self {
+        self.route_named_parameter_value_encoders
+            .entry(route_key)
+            .or_default()
+            .insert(parameter_name, value);
+
+        self
+    }
+
+    pub fn set_route_named_parameter_value_decoder(
+        &mut self,
+        route_key: K,
+        parameter_name: &'r str,
+        value: Box<ParameterValueDecoder>,
+    ) -> &mut Self {
+        self.route_named_parameter_value_decoders
+            .entry(route_key)
+            .or_default()
+            .insert(parameter_name, value);
+
+        self
+    }
+
+    pub fn insert_route(&mut self, route_key: K, template: &'r str) -> &mut Self {
+        self.insert_route_entry(route_key, template, 0, None)
+    }
+
+    // routes with a higher priority are tried before routes with a lower priority, no matter how
+    // specific the routes are. The default priority is 0.
+    pub fn insert_route_with_priority(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        priority: u32,
+    ) -> &mut Self {
+        self.insert_route_entry(route_key, template, priority, None)
+    }
+
+    // the metadata is returned by `parse_route_with_metadata` when the route matches
+    pub fn insert_route_with_metadata(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        metadata: M,
+    ) -> &mut Self {
+        self.insert_route_entry(route_key, template, 0, Some(metadata))
+    }
+
+    // the encoder and the decoder are used for every parameter of the route, like the ones of
+    // `set_route_parameter_value_encoder` and `set_route_parameter_value_decoder`
+    pub fn insert_route_with_codecs(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        encoder: Box<ParameterValueEncoder>,
+        decoder: Box<ParameterValueDecoder>,
+    ) -> &mut Self {
+        self.insert_route_entry(route_key.clone(), template, 0, None)
+            .set_route_parameter_value_encoder(route_key.clone(), encoder)
+            .set_route_parameter_value_decoder(route_key, decoder)
+    }
+
+    pub fn insert_route_with_priority_and_metadata(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        priority: u32,
+        metadata: M,
+    ) -> &mut Self {
+        self.insert_route_entry(route_key, template, priority, Some(metadata))
+    }
+
+    fn insert_route_entry(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        priority: u32,
+        metadata: Option<M>,
+    ) -> &mut Self {
+        self.try_insert_route_entry(route_key, template, priority, metadata)
+            .unwrap_or_else(|_| panic!("ambiguous route"));
+
+        self
+    }
+
+    // like `insert_route`, but returns the key of the existing route instead of panicking when the
+    // route is ambiguous. The router is not changed in that case.
+    #[cfg(feature = "openapi")]
+    pub(crate) fn try_insert_route(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+    ) -> Result<&mut Self, K> {
+        self.try_insert_route_entry(route_key, template, 0, None)?;
+
+        Ok(self)
+    }
+
+    fn try_insert_route_entry(
+        &mut self,
+        route_key: K,
+        template: &'r str,
+        priority: u32,
+        metadata: Option<M>,
+    ) -> Result<(), K> {
+        let (template_pairs, template_parameter_encodings) = self.parse_template(template);
+
+        let leaf_node_rc = route_node_insert(
+            self.root_node_rc.clone(),
+            route_key.clone(),
+            &template_pairs,
+            |parameter_name| self.find_parameter_value_separators(parameter_name),
+            priority,
+        )?;
+
+        if !template_parameter_encodings.is_empty() {
+            self.template_parameter_encodings
+                .insert(route_key.clone(), template_parameter_encodings);
+        }
+        self.insert_leaf_node(route_key, leaf_node_rc, metadata);
+
+        Ok(())
+    }
+
+    // another key for an existing route. Stringifying the alias stringifies the route, parsing
+    // always returns the key of the route.
+    #[cfg(feature = "openapi")]
+    pub(crate) fn insert_route_alias(&mut self, alias: K, route_key: K) -> &mut Self {
+        assert!(self.leaf_nodes_rc.contains_key(&route_key));
+        self.route_aliases.insert(alias, route_key);
+
+        self
+    }
+
+    // the aliases of a route, in no particular order
+    #[cfg(feature = "openapi")]
+    pub(crate) fn route_aliases_of(&self, route_key: &K) -> Vec<&K> {
+        self.route_aliases
+            .iter()
+            .filter(|(_alias, aliased_route_key)| *aliased_route_key == route_key)
+            .map(|(alias, _aliased_route_key)| alias)
+            .collect()
+    }
+
+    // the key of the route that an alias refers to, or the key itself
+    fn resolve_route_key<'k>(&'k self, route_key: &'k K) -> &'k K {
+        self.route_aliases.get(route_key).unwrap_or(route_key)
+    }
+
+    // mount the routes of another router under a prefix, the prefix may have parameters. The
+    // parameters of the prefix are part of every mounted route, so they are returned when parsing
+    // and needed when stringifying. The metadata and the encoders and decoders of the routes are
+    // moved to this router, the encoders and decoders for parameter names of the other router only
+    // apply to the mounted routes. All other options of this router apply. Fails with the first
+    // mounted route that conflicts with a route of this router, nothing is mounted in that case.
+    pub fn mount(
+        &mut self,
+        prefix_template: &'r str,
+        sub_router: Router<'r, K, M>,
+    ) -> Result<&mut Self, MergeConflict<K>> {
+        self.mount_with_key_mapping(prefix_template, sub_router, |route_key| route_key)
+    }
+
+    // like `mount`, but the keys of the other router are mapped to keys of this router, so the
+    // other router may use a different type of key, or the keys may be namespaced
+    pub fn mount_with_key_mapping<L: Eq + Hash + Clone>(
+        &mut self,
+        prefix_template: &'r str,
+        sub_router: Router<'r, L, M>,
+        map_route_key: impl Fn(L) -> K,
+    ) -> Result<&mut Self, MergeConflict<K>> {
+        match self
+            .graft_routes(prefix_template, sub_router, map_route_key, true)
+            .into_iter()
+            .next()
+        {
+            Some(conflict) => Err(conflict),
+            None => Ok(self),
+        }
+    }
+
+    // add the routes of another router to this router. Routes that are in both routers with the
+    // same template are kept as they are in this router. Routes that conflict with a route of this
+    // router are not added and are returned instead.
+    pub fn merge(&mut self, other: Router<'r, K, M>) -> Vec<MergeConflict<K>> {
+        self.graft_routes("", other, |route_key| route_key, false)
+    }
+
+    // the routes that were added, removed or that have a changed template in the other router
+    pub fn diff<N>(&self, other: &Router<'_, K, N>) -> RouterDiff<K> {
+        let route_templates = self.routes();
+        let other_route_templates = other.routes();
+        let templates: HashMap<_, _> = route_templates
+            .iter()
+            .map(|(route_key, template, _route_parameter_names)| (*route_key, template))
+            .collect();
+        let other_templates: HashMap<_, _> = other_route_templates
+            .iter()
+            .map(|(route_key, template, _route_parameter_names)| (*route_key, template))
+            .collect();
+
+        let mut diff = RouterDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for (route_key, template, _route_parameter_names) in route_templates.iter() {
+            match other_templates.get(route_key) {
+                Some(other_template) if *other_template != template => {
+                    diff.changed.push((
+                        (*route_key).clone(),
+                        template.clone(),
+                        (*other_template).clone(),
+                    ));
+                }
+                Some(_) => {}
+                None => diff.removed.push(((*route_key).clone(), template.clone())),
+            }
+        }
+        for (route_key, template, _route_parameter_names) in other_route_templates.iter() {
+            if !templates.contains_key(route_key) {
+                diff.added.push(((*route_key).clone(), template.clone()));
+            }
+        }
+
+        diff
+    }
+
+    // graft the routes of another router under a prefix, returns the routes that could not be
+    // grafted because they conflict with a route of this router. If `atomic` is set, no route is
+    // grafted when there is a conflict.
+    fn graft_routes<L: Eq + Hash + Clone>(
+        &mut self,
+        prefix_template: &'r str,
+        mut sub_router: Router<'r, L, M>,
+        map_route_key: impl Fn(L) -> K,
+        atomic: bool,
+    ) -> Vec<MergeConflict<K>> {
+        let (prefix_template_pairs, prefix_parameter_encodings) =
+            self.parse_template(prefix_template);
+        // the prefix as it will be in the reconstructed templates of the grafted routes
+        let prefix_template = pairs_template(
+            prefix_template_pairs.iter().cloned(),
+            Some(&prefix_parameter_encodings),
+        );
+
+        // the routes are grafted into a copy of the trie, so this router does not change until we
+        // know which routes are grafted
+        let root_node_rc = route_node_clone(&self.root_node_rc);
+
+        // sorted by template, so the conflicts are always in the same order
+        let mut sub_routes: Vec<_> = sub_router
+            .leaf_nodes_rc
+            .iter()
+            .map(|(sub_route_key, sub_leaf_node_rc)| {
+                (
+                    sub_route_key,
+                    sub_leaf_node_rc,
+                    sub_router.route_template(sub_route_key, sub_leaf_node_rc),
+                )
+            })
+            .collect();
+        sub_routes.sort_by(|(_, _, a), (_, _, b)| a.cmp(b));
+
+        let mut conflicts = Vec::new();
+        // the templates of the routes that are grafted or that are in both routers
+        let mut templates: HashMap<K, String> = HashMap::new();
+        let mut grafted_routes = Vec::new();
+        for (sub_route_key, sub_leaf_node_rc, sub_template) in sub_routes {
+            let route_key = map_route_key(sub_route_key.clone());
+            let template = format!("{}{}", prefix_template, sub_template);
+
+            let existing_template = match self.leaf_nodes_rc.get(&route_key) {
+                Some(leaf_node_rc) => Some(self.route_template(&route_key, leaf_node_rc)),
+                None => templates.get(&route_key).cloned(),
+            };
+            if let Some(existing_template) = existing_template {
+                if existing_template != template {
+                    conflicts.push(MergeConflict::DuplicateRouteKey {
+                        route_key,
+                        template: existing_template,
+                        other_template: template,
+                    });
+                } else {
+                    templates.insert(route_key, template);
+                }
+                continue;
+            }
+
+            match route_node_graft(
+                root_node_rc.clone(),
+                route_key.clone(),
+                &prefix_template_pairs,
+                |parameter_name| self.find_parameter_value_separators(parameter_name),
+                sub_leaf_node_rc.clone(),
+            ) {
+                Ok(leaf_node_rc) => {
+                    templates.insert(route_key.clone(), template);
+                    grafted_routes.push((sub_route_key.clone(), route_key, leaf_node_rc));
+                }
+                Err(RouteNodeGraftError::AmbiguousRoute(existing_route_key)) => {
+                    conflicts.push(MergeConflict::AmbiguousRoute {
+                        route_key: existing_route_key,
+                        other_route_key: route_key,
+                        other_template: template,
+                    })
+                }
+                Err(RouteNodeGraftError::AdjacentParameters) => {
+                    conflicts.push(MergeConflict::AdjacentParameters {
+                        route_key,
+                        template,
+                    })
+                }
+            }
+        }
+
+        if atomic && !conflicts.is_empty() {
+            return conflicts;
         }
-        fn parameter_decoder(value: &str) -> Cow<str> {
-            urlencoding::decode(value).unwrap_or(Cow::Borrowed(value))
+
+        // the routes of this router have their leaf nodes in the copy now
+        for leaf_node_rc in route_node_leaves(&root_node_rc) {
+            let route_key = leaf_node_rc.borrow().route_key.clone().unwrap();
+            if let Some(existing_leaf_node_rc) = self.leaf_nodes_rc.get_mut(&route_key) {
+                *existing_leaf_node_rc = leaf_node_rc;
+            }
+        }
+        self.root_node_rc = root_node_rc;
+
+        // the codecs for parameter names of the other router become codecs for the parameters of
+        // the grafted routes, so they do not apply to the routes of this router
+        let named_parameter_value_encoders: HashMap<_, Rc<ParameterValueEncoder>> = sub_router
+            .named_parameter_value_encoders
+            .drain()
+            .map(|(parameter_name, encoder)| (parameter_name, Rc::from(encoder)))
+            .collect();
+        let named_parameter_value_decoders: HashMap<_, Rc<ParameterValueDecoder>> = sub_router
+            .named_parameter_value_decoders
+            .drain()
+            .map(|(parameter_name, decoder)| (parameter_name, Rc::from(decoder)))
+            .collect();
+
+        for (sub_route_key, route_key, leaf_node_rc) in grafted_routes {
+            let sub_leaf_node_rc = &sub_router.leaf_nodes_rc[&sub_route_key];
+            let sub_route_index = sub_leaf_node_rc.borrow().route_index;
+            let sub_route_parameter_names = sub_leaf_node_rc.borrow().route_parameter_names.clone();
+
+            let metadata = sub_router.route_metadata[sub_route_index].take();
+            self.insert_leaf_node(route_key.clone(), leaf_node_rc, metadata);
+
+            let mut template_parameter_encodings = prefix_parameter_encodings.clone();
+            if let Some(sub_template_parameter_encodings) = sub_router
+                .template_parameter_encodings
+                .remove(&sub_route_key)
+            {
+                template_parameter_encodings.extend(sub_template_parameter_encodings);
+            }
+            if !template_parameter_encodings.is_empty() {
+                self.template_parameter_encodings
+                    .insert(route_key.clone(), template_parameter_encodings);
+            }
+
+            // a codec of the route has precedence over the codecs for parameter names
+            if let Some(encoder) = sub_router
+                .route_parameter_value_encoders
+                .remove(&sub_route_key)
+            {
+                self.route_parameter_value_encoders
+                    .insert(route_key.clone(), encoder);
+            } else {
+                let encoders: HashMap<_, _> = sub_route_parameter_names
+                    .iter()
+                    .filter_map(|parameter_name| {
+                        let encoder = named_parameter_value_encoders.get(parameter_name)?;
+                        Some((*parameter_name, share_encoder(encoder.clone())))
+                    })
+                    .collect();
+                if !encoders.is_empty() {
+                    self.route_named_parameter_value_encoders
+                        .insert(route_key.clone(), encoders);
+                }
+            }
+
+            let mut decoders = sub_router
+                .route_named_parameter_value_decoders
+                .remove(&sub_route_key)
+                .unwrap_or_default();
+            if let Some(decoder) = sub_router
+                .route_parameter_value_decoders
+                .remove(&sub_route_key)
+            {
+                self.route_parameter_value_decoders
+                    .insert(route_key.clone(), decoder);
+            } else {
+                for parameter_name in sub_route_parameter_names.iter() {
+                    if let Some(decoder) = named_parameter_value_decoders.get(parameter_name) {
+                        decoders
+                            .entry(parameter_name)
+                            .or_insert_with(|| share_decoder(decoder.clone()));
+                    }
+                }
+            }
+            if !decoders.is_empty() {
+                self.route_named_parameter_value_decoders
+                    .insert(route_key, decoders);
+            }
         }
 
-        let parameter_value_encoder = Box::new(parameter_encoder);
-        let parameter_value_decoder = Box::new(parameter_decoder);
+        for (sub_alias, sub_route_key) in sub_router.route_aliases.into_iter() {
+            let alias = map_route_key(sub_alias);
+            let route_key = map_route_key(sub_route_key);
+            if templates.contains_key(&route_key) && !self.contains_route(&alias) {
+                self.route_aliases.insert(alias, route_key);
+            }
+        }
+
+        conflicts
+    }
+
+    // the template pairs of a template, and the encoding of the parameters that are not encoded
+    // as a component
+    #[allow(clippy::type_complexity)]
+    fn parse_template(
+        &self,
+        template: &'r str,
+    ) -> (
+        Vec<(&'r str, Option<&'r str>)>,
+        HashMap<&'r str, TemplateParameterEncoding>,
+    ) {
+        if self.template_operators {
+            let template_parts =
+                parse_template_expression_parts(template, self.parameter_placeholder_re);
+
+            let template_parameter_encodings = template_parts
+                .iter()
+                .filter_map(|template_part| match *template_part {
+                    TemplatePart::Parameter(name, encoding)
+                        if encoding != TemplateParameterEncoding::Component =>
+                    {
+                        Some((name, encoding))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            (
+                template_parts_to_pairs(&template_parts),
+                template_parameter_encodings,
+            )
+        } else {
+            (
+                parse_template_pairs(template, self.parameter_placeholder_re).collect(),
+                HashMap::new(),
+            )
+        }
+    }
+
+    fn find_parameter_value_separators(&self, parameter_name: &'r str) -> &'r str {
+        self.named_parameter_value_separators
+            .get(parameter_name)
+            .cloned()
+            .unwrap_or(self.parameter_value_separators)
+    }
+
+    fn insert_leaf_node(
+        &mut self,
+        route_key: K,
+        leaf_node_rc: RouteNodeRc<'r, K>,
+        metadata: Option<M>,
+    ) {
+        // a route that is inserted again with the same key keeps it's index, if the template
+        // changed the old route does not match anymore
+        let route_index = match self.leaf_nodes_rc.get(&route_key) {
+            Some(node_rc) => {
+                if !Rc::ptr_eq(node_rc, &leaf_node_rc) {
+                    let mut node = node_rc.borrow_mut();
+                    node.route_key = None;
+                    node.route_parameter_names = Vec::new();
+                }
+                let route_index = node_rc.borrow().route_index;
+                self.route_metadata[route_index] = metadata;
+                route_index
+            }
+            None => {
+                self.route_keys.push(route_key.clone());
+                self.route_metadata.push(metadata);
+                self.route_keys.len() - 1
+            }
+        };
+        leaf_node_rc.borrow_mut().route_index = route_index;
+        self.route_aliases.remove(&route_key);
+        self.leaf_nodes_rc.insert(route_key, leaf_node_rc);
+    }
+
+    pub fn parse_route<'f>(
+        &self,
+        path: &'f str,
+    ) -> Result<(Option<&K>, RouteParameters<'r, 'f>), ParseError<'r>> {
+        let (route_index, parameters, _trailing_slash, _canonical_path) =
+            self.parse_route_index(path, None)?;
+
+        Ok((
+            route_index.map(|route_index| &self.route_keys[route_index]),
+            parameters,
+        ))
+    }
+
+    // like `parse_route`, but also return true if the route only matches when a trailing slash is
+    // added to or removed from the path. That never happens when the trailing slash policy is
+    // `Strict`.
+    pub fn parse_route_with_trailing_slash<'f>(
+        &self,
+        path: &'f str,
+    ) -> Result<(Option<&K>, RouteParameters<'r, 'f>, bool), ParseError<'r>> {
+        let (route_index, parameters, trailing_slash, _canonical_path) =
+            self.parse_route_index(path, None)?;
+
+        Ok((
+            route_index.map(|route_index| &self.route_keys[route_index]),
+            parameters,
+            trailing_slash,
+        ))
+    }
+
+    // like `parse_route`, but also return the metadata that was inserted with the route, if any
+    pub fn parse_route_with_metadata<'f>(
+        &self,
+        path: &'f str,
+    ) -> Result<(Option<&K>, Option<&M>, RouteParameters<'r, 'f>), ParseError<'r>> {
+        let (route_index, parameters, _trailing_slash, _canonical_path) =
+            self.parse_route_index(path, None)?;
+
+        match route_index {
+            Some(route_index) => Ok((
+                Some(&self.route_keys[route_index]),
+                self.route_metadata[route_index].as_ref(),
+                parameters,
+            )),
+            None => Ok((None, None, parameters)),
+        }
+    }
+
+    // like `parse_route`, but when the trailing slash policy is `RedirectHint` also return the
+    // canonical path if the path only matches after a trailing slash is added or removed, after
+    // it is normalized or when the anchors are matched while ignoring case. The parameter values
+    // in the canonical path are written the way they are in the path.
+    pub fn parse_route_canonical<'f>(
+        &self,
+        path: &'f str,
+    ) -> Result<(Option<&K>, RouteParameters<'r, 'f>, Option<String>), ParseError<'r>> {
+        let (route_index, parameters, _trailing_slash, canonical_path) =
+            self.parse_route_index(path, None)?;
+
+        Ok((
+            route_index.map(|route_index| &self.route_keys[route_index]),
+            parameters,
+            canonical_path,
+        ))
+    }
+
+    // the index of the matched route, the parameters, if the route only matched with a trailing
+    // slash added or removed and the canonical path
+    #[allow(clippy::type_complexity)]
+    fn parse_route_index<'f>(
+        &self,
+        path: &'f str,
+        trace: Option<&mut ParseTrace<'r, 'f, K>>,
+    ) -> Result<(Option<usize>, RouteParameters<'r, 'f>, bool, Option<String>), ParseError<'r>>
+    {
+        let normalized_path = if let Some(normalized_path) = self.normalize_path(path) {
+            normalized_path
+        } else {
+            return Ok(Default::default());
+        };
+
+        // the normalized path is matched, if the trailing slash policy is not strict the route may
+        // only match when a trailing slash is added or removed
+        let (route_index, parameters, trailing_slash, canonical_path) =
+            self.parse_normalized_route(normalized_path, trace)?;
+
+        // the canonical path is the same as the path if nothing had to be changed to match it
+        let canonical_path = canonical_path.filter(|canonical_path| canonical_path != path);
+
+        Ok((route_index, parameters, trailing_slash, canonical_path))
+    }
+
+    // apply the normalization options to a path before it is matched against the anchors,
+    // returns `None` if the path is rejected
+    fn normalize_path<'f>(&self, path: &'f str) -> Option<Cow<'f, str>> {
+        fn apply<'f>(path: &mut Cow<'f, str>, normalize: impl Fn(&str) -> Cow<str>) {
+            let normalized_path = match normalize(path) {
+                Cow::Owned(normalized_path) => Some(normalized_path),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(normalized_path) = normalized_path {
+                *path = Cow::Owned(normalized_path);
+            }
+        }
+
+        if self.reject_encoded_slashes && contains_encoded_slash(path) {
+            return None;
+        }
+
+        let mut path = Cow::Borrowed(path);
+
+        if self.decode_unreserved {
+            apply(&mut path, decode_unreserved_percent_escapes);
+        }
+
+        #[cfg(feature = "unicode-normalization")]
+        if self.unicode_nfc {
+            apply(&mut path, |path| {
+                use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+                if is_nfc_quick(path.chars()) == IsNormalized::Yes {
+                    Cow::Borrowed(path)
+                } else {
+                    Cow::Owned(path.nfc().collect())
+                }
+            });
+        }
+
+        if self.collapse_duplicate_slashes {
+            apply(&mut path, collapse_duplicate_slashes);
+        }
+
+        Some(path)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_normalized_route<'f>(
+        &self,
+        path: Cow<'f, str>,
+        trace: Option<&mut ParseTrace<'r, 'f, K>>,
+    ) -> Result<(Option<usize>, RouteParameters<'r, 'f>, bool, Option<String>), ParseError<'r>>
+    {
+        match path {
+            Cow::Borrowed(path) => {
+                let (route_index, parameter_names, parameter_values, trailing_slash) =
+                    route_node_parse(
+                        self.root_node_rc.clone(),
+                        path,
+                        &self.parse_options(),
+                        trace,
+                    );
+
+                let canonical_path = route_index
+                    .filter(|_| self.trailing_slash == TrailingSlash::RedirectHint)
+                    .map(|route_index| self.canonical_path(route_index, &parameter_values));
+
+                let (route_index, parameters) =
+                    self.decode_parameters(route_index, parameter_names, parameter_values)?;
+
+                Ok((route_index, parameters, trailing_slash, canonical_path))
+            }
+            Cow::Owned(path) => {
+                // parameter values and the steps of the trace cannot borrow from a path we
+                // created, so they are copied
+                let mut path_trace = trace.as_ref().map(|_| ParseTrace::default());
+                let (route_index, parameters, trailing_slash, canonical_path) =
+                    self.parse_normalized_route(Cow::Borrowed(&path), path_trace.as_mut())?;
+                let parameters = parameters
+                    .into_iter()
+                    .map(|(name, value)| (name, Cow::Owned(value.into_owned())))
+                    .collect();
+                if let (Some(trace), Some(path_trace)) = (trace, path_trace) {
+                    trace.append(path_trace);
+                }
+
+                Ok((route_index, parameters, trailing_slash, canonical_path))
+            }
+        }
+    }
+
+    // the anchors of the route with the parameter values in between, the values are not decoded
+    // and encoded again so they are written the way they are in the path
+    fn canonical_path(&self, route_index: usize, parameter_values: &[&str]) -> String {
+        let mut parameter_values = parameter_values.iter();
+        let mut canonical_path = String::new();
+
+        for (anchor, parameter) in self
+            .route_template_pairs(&self.route_keys[route_index])
+            .unwrap()
+        {
+            if parameter.is_some() {
+                canonical_path.push_str(parameter_values.next().unwrap());
+            }
+            canonical_path.push_str(anchor);
+        }
+
+        canonical_path
+    }
+
+    // like `parse_route`, but also return every step that was taken while matching the path. The
+    // steps are recorded after the path is normalized.
+    pub fn parse_route_traced<'f>(
+        &self,
+        path: &'f str,
+    ) -> Result<(Option<&K>, RouteParameters<'r, 'f>, ParseTrace<'r, 'f, K>), ParseError<'r>> {
+        let mut trace = ParseTrace::default();
+        let (route_index, parameters, _trailing_slash, _canonical_path) =
+            self.parse_route_index(path, Some(&mut trace))?;
+
+        Ok((
+            route_index.map(|route_index| &self.route_keys[route_index]),
+            parameters,
+            trace,
+        ))
+    }
+
+    fn parse_options(&self) -> RouteNodeParseOptions {
+        RouteNodeParseOptions {
+            maximum_parameter_value_length: self.maximum_parameter_value_length,
+            case_insensitive: self.case_insensitive,
+            trailing_slash: self.trailing_slash != TrailingSlash::Strict,
+            backtrack: self.parameter_value_match != ParameterValueMatch::FirstAnchor,
+            prefer_longest: self.parameter_value_match == ParameterValueMatch::Longest,
+        }
+    }
+
+    fn decode_parameters<'f>(
+        &self,
+        route_index: Option<usize>,
+        parameter_names: Vec<&'r str>,
+        parameter_values: Vec<&'f str>,
+    ) -> Result<(Option<usize>, RouteParameters<'r, 'f>), ParseError<'r>> {
+        if let Some(route_index) = route_index {
+            let route_key = &self.route_keys[route_index];
+            let parameters = parameter_names
+                .iter()
+                .cloned()
+                .zip(parameter_values.iter().cloned())
+                .map(|(name, value)| {
+                    match self.find_parameter_value_decoder(route_key, name)(value) {
+                        Ok(value) => Ok((name, value)),
+                        Err(error) => Err(match error.downcast::<InvalidParameterValue>() {
+                            Ok(error) => ParseError::InvalidParameterValue {
+                                name,
+                                value: value.to_owned(),
+                                reason: error.0,
+                            },
+                            Err(_error) => ParseError::InvalidParameterEncoding {
+                                name,
+                                value: value.to_owned(),
+                            },
+                        }),
+                    }
+                })
+                .collect::<Result<HashMap<_, _>, _>>()?;
+
+            Ok((Some(route_index), parameters))
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    fn find_parameter_value_encoder(
+        &self,
+        route_key: &K,
+        parameter_name: &str,
+    ) -> &ParameterValueEncoder {
+        self.route_named_parameter_value_encoders
+            .get(route_key)
+            .and_then(|encoders| encoders.get(parameter_name))
+            .or_else(|| self.route_parameter_value_encoders.get(route_key))
+            .or_else(|| self.named_parameter_value_encoders.get(parameter_name))
+            .map(|encoder| encoder.as_ref())
+            .or_else(|| {
+                let encoding = self
+                    .template_parameter_encodings
+                    .get(route_key)?
+                    .get(parameter_name)?;
+                let encoder: &ParameterValueEncoder = match encoding {
+                    TemplateParameterEncoding::Component => return None,
+                    TemplateParameterEncoding::Reserved => &encode_reserved,
+                    TemplateParameterEncoding::Segments => &encode_segments,
+                };
+                Some(encoder)
+            })
+            .unwrap_or_else(|| self.parameter_value_encoder.as_ref())
+    }
+
+    // how the value of a parameter is encoded when the route is stringified, `None` if that is
+    // done by a custom encoder
+    pub(crate) fn parameter_value_encoding(
+        &self,
+        route_key: &K,
+        parameter_name: &str,
+    ) -> Option<TemplateParameterEncoding> {
+        let route_key = self.resolve_route_key(route_key);
+        let has_route_named_encoder = self
+            .route_named_parameter_value_encoders
+            .get(route_key)
+            .map(|encoders| encoders.contains_key(parameter_name))
+            .unwrap_or_default();
+        if has_route_named_encoder
+            || self.route_parameter_value_encoders.contains_key(route_key)
+            || self
+                .named_parameter_value_encoders
+                .contains_key(parameter_name)
+        {
+            return None;
+        }
+
+        match self
+            .template_parameter_encodings
+            .get(route_key)
+            .and_then(|encodings| encodings.get(parameter_name))
+        {
+            Some(encoding) if *encoding != TemplateParameterEncoding::Component => Some(*encoding),
+            _ if self.custom_parameter_value_encoder => None,
+            _ => Some(TemplateParameterEncoding::Component),
+        }
+    }
+
+    fn find_parameter_value_decoder(
+        &self,
+        route_key: &K,
+        parameter_name: &'r str,
+    ) -> &ParameterValueDecoder {
+        self.route_named_parameter_value_decoders
+            .get(route_key)
+            .and_then(|decoders| decoders.get(parameter_name))
+            .or_else(|| self.route_parameter_value_decoders.get(route_key))
+            .or_else(|| self.named_parameter_value_decoders.get(parameter_name))
+            .unwrap_or(&self.parameter_value_decoder)
+    }
+
+    pub fn stringify_route<'f>(
+        &self,
+        route_key: &K,
+        route_parameters: &'f HashMap<&'f str, &'f str>,
+    ) -> Result<Cow<'f, str>, StringifyError>
+    where
+        'r: 'f,
+    {
+        let route_key = self.resolve_route_key(route_key);
+        if let Some(node_rc) = self.leaf_nodes_rc.get(route_key) {
+            let parameter_values: Vec<_> = node_rc
+                .borrow()
+                .route_parameter_names
+                .iter()
+                .map(|parameter_name| {
+                    let parameter_value = route_parameters.get(parameter_name).unwrap();
+                    self.find_parameter_value_encoder(route_key, parameter_name)(parameter_value)
+                })
+                .collect();
+
+            let path = route_node_stringify(node_rc.clone(), parameter_values);
+
+            // duplicate slashes would be collapsed when the path is parsed, so the path would not
+            // parse to the same route and parameters. Collapsing them here would silently change
+            // the parameter values, so this is an error.
+            if self.collapse_duplicate_slashes && path.contains("//") {
+                return Err(StringifyError::DuplicateSlashes {
+                    path: path.into_owned(),
+                });
+            }
+
+            Ok(path)
+        } else {
+            Err(StringifyError::UnknownRoute)
+        }
+    }
+
+    // like `stringify_route`, but the path is a relative reference from the current path, like
+    // `../2` from `/product/1/reviews` to `/product/2`. Relative references keep working when the
+    // routes are served under a prefix that the router does not know about.
+    pub fn stringify_relative(
+        &self,
+        from_path: &str,
+        route_key: &K,
+        route_parameters: &HashMap<&str, &str>,
+    ) -> Result<String, StringifyError> {
+        let path = self.stringify_route(route_key, route_parameters)?;
+
+        Ok(relative_reference(from_path, &path))
+    }
+
+    // an absolute url of a route, the path of the route is appended to the path of the base url
+    // so a base url like `https://example.com/api/` works with or without the trailing slash.
+    // The query and the fragment of the base url are not used. Returns `None` if the route cannot
+    // be stringified or if the base url cannot be a base.
+    #[cfg(feature = "url")]
+    pub fn build_url(
+        &self,
+        route_key: &K,
+        route_parameters: &HashMap<&str, &str>,
+        base_url: &url::Url,
+    ) -> Option<url::Url> {
+        self.build_url_with_query(route_key, route_parameters, base_url, &[], None)
+    }
+
+    // like `build_url`, but also add a query, encoded as `application/x-www-form-urlencoded`, and
+    // a fragment. The query is added after the query of the route, if it has one, and the
+    // fragment replaces the fragment of the route.
+    #[cfg(feature = "url")]
+    pub fn build_url_with_query(
+        &self,
+        route_key: &K,
+        route_parameters: &HashMap<&str, &str>,
+        base_url: &url::Url,
+        query: &[(&str, &str)],
+        fragment: Option<&str>,
+    ) -> Option<url::Url> {
+        if base_url.cannot_be_a_base() {
+            return None;
+        }
+
+        let path = self.stringify_route(route_key, route_parameters).ok()?;
+
+        // templates with operators like `{?query}` or `{#line}` put a query or a fragment in the
+        // path, these are not part of the path of the url
+        let (path, route_fragment) = match path.split_once('#') {
+            Some((path, route_fragment)) => (path, Some(route_fragment)),
+            None => (&*path, None),
+        };
+        let (path, route_query) = match path.split_once('?') {
+            Some((path, route_query)) => (path, Some(route_query)),
+            None => (path, None),
+        };
+
+        // join the paths with exactly one slash
+        let base_path = base_url.path();
+        let base_path = base_path.strip_suffix('/').unwrap_or(base_path);
+        let path = path.strip_prefix('/').unwrap_or(path);
+
+        let mut url = base_url.clone();
+        url.set_path(&format!("{}/{}", base_path, path));
+        url.set_query(route_query);
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url.set_fragment(fragment.or(route_fragment));
+
+        Some(url)
+    }
+
+    // the metadata that was inserted with the route, if any
+    pub fn route_metadata(&self, route_key: &K) -> Option<&M> {
+        let route_index = self
+            .leaf_nodes_rc
+            .get(self.resolve_route_key(route_key))?
+            .borrow()
+            .route_index;
+
+        self.route_metadata[route_index].as_ref()
+    }
+
+    pub fn to_tree_string(&self) -> String
+    where
+        K: Debug,
+    {
+        route_node_print_tree(self.root_node_rc.clone())
+    }
+
+    pub fn to_dot(&self) -> String
+    where
+        K: Debug,
+    {
+        route_node_print_dot(self.root_node_rc.clone())
+    }
+
+    // every route with a template in the `{name}` syntax and the names of the parameters in the
+    // order they appear in the template. The template is reconstructed from the trie, see
+    // `template_of`. Sorted by template so the order is stable.
+    pub fn routes(&self) -> Vec<(&K, String, Vec<&'r str>)> {
+        let mut routes: Vec<_> = self
+            .leaf_nodes_rc
+            .iter()
+            .map(|(route_key, node_rc)| {
+                let template = self.route_template(route_key, node_rc);
+
+                (
+                    route_key,
+                    template,
+                    node_rc.borrow().route_parameter_names.clone(),
+                )
+            })
+            .collect();
+        routes.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+
+        routes
+    }
+
+    pub fn contains_route(&self, route_key: &K) -> bool {
+        self.leaf_nodes_rc
+            .contains_key(self.resolve_route_key(route_key))
+    }
+
+    // the template of a route in the `{name}` syntax, reconstructed from the trie so it always
+    // matches what the router does. An rfc 6570 expression is written as the simplest expression
+    // with the same encoding, so `/users{/id}{?q}` becomes `/users/{id}?q={q}` while `{+path}`
+    // and `{/segments*}` stay as they are. The reconstructed template results in the same route.
+    pub fn template_of(&self, route_key: &K) -> Option<String> {
+        let route_key = self.resolve_route_key(route_key);
+        self.leaf_nodes_rc
+            .get(route_key)
+            .map(|node_rc| self.route_template(route_key, node_rc))
+    }
+
+    fn route_template(&self, route_key: &K, leaf_node_rc: &RouteNodeRc<'r, K>) -> String {
+        let template_pairs = route_node_pairs(leaf_node_rc.clone())
+            .into_iter()
+            .map(|(anchor, parameter, _parameter_value_separators)| (anchor, parameter));
+
+        pairs_template(
+            template_pairs,
+            self.template_parameter_encodings.get(route_key),
+        )
+    }
+
+    // the anchors and parameters of a route as they are in the trie, every parameter comes before
+    // it's anchor. Stringifying the route concatenates these.
+    pub(crate) fn route_template_pairs(
+        &self,
+        route_key: &K,
+    ) -> Option<Vec<(&'r str, Option<&'r str>)>> {
+        let node_rc = self.leaf_nodes_rc.get(self.resolve_route_key(route_key))?;
+
+        Some(
+            route_node_pairs(node_rc.clone())
+                .into_iter()
+                .map(|(anchor, parameter, _parameter_value_separators)| (anchor, parameter))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn collapses_duplicate_slashes(&self) -> bool {
+        self.collapse_duplicate_slashes
+    }
+
+    // the number of routes
+    pub fn len(&self) -> usize {
+        self.leaf_nodes_rc.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_nodes_rc.is_empty()
+    }
+}
+
+// a template in the `{name}` syntax from template pairs. A parameter that is not encoded as a
+// component is written as the rfc 6570 expression that encodes it that way.
+fn pairs_template<'r>(
+    template_pairs: impl IntoIterator<Item = (&'r str, Option<&'r str>)>,
+    template_parameter_encodings: Option<&HashMap<&'r str, TemplateParameterEncoding>>,
+) -> String {
+    let mut template = String::new();
+
+    for (anchor, parameter_name) in template_pairs {
+        if let Some(parameter_name) = parameter_name {
+            let encoding = template_parameter_encodings
+                .and_then(|encodings| encodings.get(parameter_name))
+                .cloned()
+                .unwrap_or(TemplateParameterEncoding::Component);
+
+            match encoding {
+                TemplateParameterEncoding::Component => {
+                    template.push_str(&format!("{{{}}}", parameter_name))
+                }
+                TemplateParameterEncoding::Reserved => {
+                    template.push_str(&format!("{{+{}}}", parameter_name))
+                }
+                TemplateParameterEncoding::Segments => {
+                    // the slash in front of the segments is part of the expression
+                    let slash = template.pop();
+                    debug_assert_eq!(slash, Some('/'));
+                    template.push_str(&format!("{{/{}*}}", parameter_name))
+                }
+            }
+        }
+        template.push_str(anchor);
+    }
+
+    template
+}
+
+// a codec of a router for every route that it is grafted to
+fn share_encoder(encoder: Rc<ParameterValueEncoder>) -> Box<ParameterValueEncoder> {
+    Box::new(move |value| encoder(value))
+}
+
+fn share_decoder(decoder: Rc<ParameterValueDecoder>) -> Box<ParameterValueDecoder> {
+    Box::new(move |value| decoder(value))
+}
+
+impl<'r, K: Eq + Hash + Clone, M> Default for Router<'r, K, M> {
+    fn default() -> Self {
+        Self::new_with_metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec;
+    use crate::trace::ParseTraceOutcome;
+    use itertools::Itertools;
+    use std::collections::HashSet;
+
+    #[test]
+    fn readme() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("all-products", "/product/all")
+            .insert_route("product-detail", "/product/{id}");
+
+        // And now we can parse routes!
+
+        {
+            let (route_key, route_parameters) = router.parse_route("/not-found").unwrap();
+            assert_eq!(route_key, None);
+            assert_eq!(route_parameters, Default::default());
+        }
+
+        {
+            let (route_key, route_parameters) = router.parse_route("/product/all").unwrap();
+            assert_eq!(route_key, Some(&"all-products"));
+            assert_eq!(route_parameters, Default::default());
+        }
+
+        {
+            let (route_key, route_parameters) = router.parse_route("/product/1").unwrap();
+            assert_eq!(route_key, Some(&"product-detail"));
+            assert_eq!(
+                route_parameters,
+                vec![("id", "1")]
+                    .into_iter()
+                    .map(|(k, v)| (k, Cow::Borrowed(v)))
+                    .collect()
+            );
+        }
+
+        // And we can stringify routes
+
+        {
+            let route_parameters: HashMap<_, _> = vec![].into_iter().collect();
+            let path = router.stringify_route(&"all-products", &route_parameters);
+            assert_eq!(path.unwrap().into_owned(), "/product/all".to_owned());
+        }
+
+        {
+            let route_parameters: HashMap<_, _> = vec![("id", "2")].into_iter().collect();
+            let path = router.stringify_route(&"product-detail", &route_parameters);
+            assert_eq!(path.unwrap().into_owned(), "/product/2".to_owned());
+        }
+    }
+
+    #[test]
+    fn router_traced() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("all-products", "/product/all")
+            .insert_route("product-detail", "/product/{id}")
+            .insert_route("product-reviews", "/product/{id}/reviews");
+
+        let (route_key, _route_parameters, trace) =
+            router.parse_route_traced("/product/1").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(
+            trace.to_string(),
+            [
+                r#""" at "/product/1": anchor matched"#,
+                r#"  "/product/" at "/product/1": anchor matched"#,
+                r#"    {}"/reviews" at "1": anchor not found (window "1")"#,
+                r#"    "all" at "1": anchor mismatch"#,
+                r#"    {}"" at "1": parameter value "1" (window "1")"#,
+                r#"    {}"" at "": route matched "product-detail""#,
+                "",
+            ]
+            .join("\n")
+        );
+
+        router.set_maximum_parameter_value_length(2);
+
+        let (route_key, _route_parameters, trace) =
+            router.parse_route_traced("/product/123/reviews").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(
+            trace.steps[2].outcome,
+            ParseTraceOutcome::AnchorNotFound {
+                window: "123/review".into()
+            }
+        );
+    }
+
+    #[test]
+    fn router_normalization() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("product-detail", "/product/{id}/detail")
+            .insert_route("product-reviews", "/product/{id}/reviews/")
+            .insert_route("file", "/file/{name}.JSON");
+
+        assert_eq!(router.parse_route("/Product/1/detail").unwrap().0, None);
+        assert_eq!(router.parse_route("//product/1/detail").unwrap().0, None);
+        assert_eq!(router.parse_route("/product/1/reviews").unwrap().0, None);
+
+        router
+            .set_case_insensitive(true)
+            .set_collapse_duplicate_slashes(true)
+            .set_trailing_slash(TrailingSlash::Ignore);
+
+        let (route_key, route_parameters) = router.parse_route("/PRODUCT/1/Detail").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(route_parameters["id"], "1");
+
+        let (route_key, route_parameters) = router.parse_route("/file/a.json").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["name"], "a");
+
+        let (route_key, route_parameters) = router.parse_route("//product//1//reviews").unwrap();
+        assert_eq!(route_key, Some(&"product-reviews"));
+        assert_eq!(route_parameters["id"], "1");
+
+        // the trace is of the normalized path
+        let (route_key, route_parameters, trace) =
+            router.parse_route_traced("//product//1//reviews").unwrap();
+        assert_eq!(route_key, Some(&"product-reviews"));
+        assert_eq!(route_parameters["id"], "1");
+        assert_eq!(trace.steps[0].path, "/product/1/reviews");
+
+        let (route_key, route_parameters, canonical_path) =
+            router.parse_route_canonical("/product/1/detail/").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(route_parameters["id"], "1");
+        assert_eq!(canonical_path, None);
+
+        router.set_trailing_slash(TrailingSlash::RedirectHint);
+
+        let (route_key, _route_parameters, canonical_path) =
+            router.parse_route_canonical("/Product//1/reviews").unwrap();
+        assert_eq!(route_key, Some(&"product-reviews"));
+        assert_eq!(canonical_path, Some("/product/1/reviews/".to_owned()));
+
+        let (route_key, _route_parameters, canonical_path) =
+            router.parse_route_canonical("/product/1/reviews/").unwrap();
+        assert_eq!(route_key, Some(&"product-reviews"));
+        assert_eq!(canonical_path, None);
+
+        // a path with duplicate slashes would not parse to the same parameters
+        let route_parameters = vec![("id", "")].into_iter().collect();
+        assert_eq!(
+            router.stringify_route(&"product-detail", &route_parameters),
+            Err(StringifyError::DuplicateSlashes {
+                path: "/product//detail".to_owned()
+            })
+        );
+
+        router
+            .set_route_parameter_value_encoder("product-detail", Box::new(codec::encode_segments));
+
+        let route_parameters = vec![("id", "a//b")].into_iter().collect();
+        assert_eq!(
+            router.stringify_route(&"product-detail", &route_parameters),
+            Err(StringifyError::DuplicateSlashes {
+                path: "/product/a//b/detail".to_owned()
+            })
+        );
+        assert_eq!(
+            router.stringify_route(&"unknown", &route_parameters),
+            Err(StringifyError::UnknownRoute)
+        );
+
+        let route_parameters = vec![("id", "a/b")].into_iter().collect();
+        assert_eq!(
+            router
+                .stringify_route(&"product-detail", &route_parameters)
+                .unwrap(),
+            "/product/a/b/detail"
+        );
+    }
+
+    #[test]
+    fn router_traced_normalization() {
+        let options: Vec<fn(&mut Router<'static, &'static str>)> = vec![
+            |router| {
+                router.set_case_insensitive(true);
+            },
+            |router| {
+                router.set_collapse_duplicate_slashes(true);
+            },
+            |router| {
+                router.set_trailing_slash(TrailingSlash::Ignore);
+            },
+            |router| {
+                router.set_trailing_slash(TrailingSlash::RedirectHint);
+            },
+            |router| {
+                router.set_decode_unreserved(true);
+            },
+            |router| {
+                router.set_reject_encoded_slashes(true);
+            },
+            #[cfg(feature = "unicode-normalization")]
+            |router| {
+                router.set_unicode_nfc(true).set_decode_unreserved(true);
+            },
+        ];
+
+        let paths = [
+            "/product/1/detail",
+            "/PRODUCT/1/Detail",
+            "//product//1//detail",
+            "/product/1/detail/",
+            "/caf%C3%A9/menu",
+            "/cafe\u{301}/menu",
+            "/cafe%CC%81/menu",
+            "/files/a%2Fb",
+            "/files/%7Ea",
+        ];
+
+        for set_option in options {
+            let mut router = Router::new();
+            router
+                .insert_route("product-detail", "/product/{id}/detail")
+                .insert_route("menu", "/caf\u{e9}/menu")
+                .insert_route("file", "/files/{name}");
+            set_option(&mut router);
+
+            for path in paths {
+                let (route_key, route_parameters, _trace) =
+                    router.parse_route_traced(path).unwrap();
+                assert_eq!(
+                    (route_key, route_parameters),
+                    router.parse_route(path).unwrap(),
+                    "{}",
+                    path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn router_trailing_slash() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("one", "/a")
+            .insert_route("two", "/a/{x}/")
+            .insert_route("three", "/b/")
+            .insert_route("four", "/c/{y}/{z}/");
+
+        assert_eq!(router.parse_route("/c/3/4").unwrap().0, None);
+
+        router.set_trailing_slash(TrailingSlash::RedirectHint);
+
+        let (route_key, route_parameters, canonical_path) =
+            router.parse_route_canonical("/c/3/4").unwrap();
+        assert_eq!(route_key, Some(&"four"));
+        assert_eq!(
+            route_parameters,
+            vec![("y", "3"), ("z", "4")]
+                .into_iter()
+                .map(|(k, v)| (k, Cow::Borrowed(v)))
+                .collect(),
+        );
+        assert_eq!(canonical_path, Some("/c/3/4/".to_owned()));
+
+        let (route_key, _route_parameters, canonical_path) =
+            router.parse_route_canonical("/a/").unwrap();
+        assert_eq!(route_key, Some(&"one"));
+        assert_eq!(canonical_path, Some("/a".to_owned()));
+
+        let (route_key, _route_parameters, canonical_path) =
+            router.parse_route_canonical("/b").unwrap();
+        assert_eq!(route_key, Some(&"three"));
+        assert_eq!(canonical_path, Some("/b/".to_owned()));
+
+        // an exact match always wins
+        let (route_key, route_parameters, canonical_path) =
+            router.parse_route_canonical("/a/1/").unwrap();
+        assert_eq!(route_key, Some(&"two"));
+        assert_eq!(route_parameters["x"], "1");
+        assert_eq!(canonical_path, None);
+
+        let (route_key, _route_parameters, trace) = router.parse_route_traced("/b").unwrap();
+        assert_eq!(route_key, Some(&"three"));
+        assert!(trace
+            .steps
+            .iter()
+            .any(|step| step.outcome == ParseTraceOutcome::TrailingSlashMismatch("three")));
+
+        // an exact match is not redirected, even if the parameter values would be encoded
+        // differently when the route is stringified
+        router.insert_route("product", "/product/{id}");
+        for path in ["/product/a:b", "/product/a+b", "/product/%7Ex"] {
+            let (route_key, _route_parameters, canonical_path) =
+                router.parse_route_canonical(path).unwrap();
+            assert_eq!(route_key, Some(&"product"), "{}", path);
+            assert_eq!(canonical_path, None, "{}", path);
+        }
+
+        // the values are kept as they are when the trailing slash is added
+        let (route_key, route_parameters, canonical_path) =
+            router.parse_route_canonical("/c/a:b/%7Ex").unwrap();
+        assert_eq!(route_key, Some(&"four"));
+        assert_eq!(route_parameters["y"], "a:b");
+        assert_eq!(route_parameters["z"], "~x");
+        assert_eq!(canonical_path, Some("/c/a:b/%7Ex/".to_owned()));
+
+        // the other policies tell if a trailing slash was toggled, but have no canonical path
+        for (trailing_slash, trailing_slash_toggled) in [
+            (TrailingSlash::Ignore, true),
+            (TrailingSlash::Strict, false),
+        ] {
+            router.set_trailing_slash(trailing_slash);
+
+            let (route_key, _route_parameters, trailing_slash) =
+                router.parse_route_with_trailing_slash("/c/3/4").unwrap();
+            assert_eq!(route_key.is_some(), trailing_slash_toggled);
+            assert_eq!(trailing_slash, trailing_slash_toggled);
+
+            let (route_key, _route_parameters, trailing_slash) =
+                router.parse_route_with_trailing_slash("/c/3/4/").unwrap();
+            assert_eq!(route_key, Some(&"four"));
+            assert!(!trailing_slash);
+
+            assert_eq!(router.parse_route_canonical("/c/3/4").unwrap().2, None);
+        }
+    }
+
+    #[test]
+    fn router_percent_decoding() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("menu", "/café/menu")
+            .insert_route("greeting", "/日本/{name}")
+            .insert_route("file", "/files/{path}")
+            .insert_route("user", "/~{user}");
+
+        assert_eq!(router.parse_route("/caf%C3%A9/menu").unwrap().0, None);
+        assert_eq!(router.parse_route("/café/menu").unwrap().0, Some(&"menu"));
+
+        router.set_decode_unreserved(true);
+
+        assert_eq!(
+            router.parse_route("/caf%C3%A9/menu").unwrap().0,
+            Some(&"menu")
+        );
+        assert_eq!(
+            router.parse_route("/caf%c3%a9/menu").unwrap().0,
+            Some(&"menu")
+        );
+
+        let (route_key, route_parameters) = router
+            .parse_route("/%E6%97%A5%E6%9C%AC/%E5%A4%AA%E9%83%8E")
+            .unwrap();
+        assert_eq!(route_key, Some(&"greeting"));
+        assert_eq!(route_parameters["name"], "太郎");
+
+        let (route_key, route_parameters) = router.parse_route("/%7Eelmer").unwrap();
+        assert_eq!(route_key, Some(&"user"));
+        assert_eq!(route_parameters["user"], "elmer");
+
+        // reserved characters and the percent sign stay encoded until the value is decoded
+        let (route_key, route_parameters) = router.parse_route("/files/a%2Fb%25c").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["path"], "a/b%c");
+
+        router.set_reject_encoded_slashes(true);
+
+        assert_eq!(router.parse_route("/files/a%2Fb").unwrap().0, None);
+        assert_eq!(router.parse_route("/files/a%2fb").unwrap().0, None);
+        assert_eq!(router.parse_route("/files/a%20b").unwrap().0, Some(&"file"));
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn router_unicode_nfc() {
+        let mut router = Router::new();
+
+        router.insert_route("menu", "/caf\u{e9}/menu");
+
+        assert_eq!(router.parse_route("/cafe\u{301}/menu").unwrap().0, None);
+
+        router.set_unicode_nfc(true);
+
+        assert_eq!(
+            router.parse_route("/cafe\u{301}/menu").unwrap().0,
+            Some(&"menu")
+        );
+
+        router.set_decode_unreserved(true);
+
+        assert_eq!(
+            router.parse_route("/cafe%CC%81/menu").unwrap().0,
+            Some(&"menu")
+        );
+    }
+
+    #[test]
+    fn router_parameter_encoding() {
+        let mut router = Router::new();
+
+        router.insert_route("product-detail", "/product/{id}");
+
+        let (route_key, route_parameters) = router.parse_route("/product/%C3%A9").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(route_parameters["id"], "é");
+
+        assert_eq!(
+            router.parse_route("/product/%FF"),
+            Err(ParseError::InvalidParameterEncoding {
+                name: "id",
+                value: "%FF".to_owned()
+            })
+        );
+
+        router.set_parameter_value_decoder(Box::new(|value| {
+            if value.chars().all(|c| c.is_ascii_digit()) {
+                Ok(Cow::Borrowed(value))
+            } else {
+                Err("not a number".into())
+            }
+        }));
+
+        let (route_key, route_parameters) = router.parse_route("/product/123").unwrap();
+        assert_eq!(route_key, Some(&"product-detail"));
+        assert_eq!(route_parameters["id"], "123");
+
+        assert_eq!(
+            router.parse_route("/product/abc"),
+            Err(ParseError::InvalidParameterEncoding {
+                name: "id",
+                value: "abc".to_owned()
+            })
+        );
+
+        // a decoder can tell that a value is encoded correctly, but not allowed
+        router.set_parameter_value_decoder(Box::new(|value| {
+            let value = decode_component(value)?;
+            if value.len() > 3 {
+                return Err(InvalidParameterValue("too long".to_owned()).into());
+            }
+            Ok(value)
+        }));
+
+        assert_eq!(
+            router.parse_route("/product/%C3%A9%C3%A9"),
+            Err(ParseError::InvalidParameterValue {
+                name: "id",
+                value: "%C3%A9%C3%A9".to_owned(),
+                reason: "too long".to_owned(),
+            })
+        );
+        assert_eq!(
+            router.parse_route("/product/%FF"),
+            Err(ParseError::InvalidParameterEncoding {
+                name: "id",
+                value: "%FF".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn router_parameter_codecs() {
+        let mut router = Router::new();
+
+        router
+            .insert_route("file", "/files/{path}/raw")
+            .insert_route("search", "/search/{query}/{page}")
+            .insert_route("tag", "/tags/{query}")
+            .set_route_parameter_value_encoder("file", Box::new(codec::encode_segments))
+            .set_named_parameter_value_encoder("query", Box::new(codec::encode_form))
+            .set_named_parameter_value_decoder("query", Box::new(codec::decode_form));
+
+        let route_parameters = vec![("path", "a b/c")].into_iter().collect();
+        let path = router.stringify_route(&"file", &route_parameters).unwrap();
+        assert_eq!(path, "/files/a%20b/c/raw");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["path"], "a b/c");
+
+        let route_parameters = vec![("query", "a b+c"), ("page", "1 2")]
+            .into_iter()
+            .collect();
+        let path = router
+            .stringify_route(&"search", &route_parameters)
+            .unwrap();
+        assert_eq!(path, "/search/a+b%2Bc/1%202");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"search"));
+        assert_eq!(route_parameters["query"], "a b+c");
+        assert_eq!(route_parameters["page"], "1 2");
+
+        // the route decoder has precedence over the decoder for the parameter name
+        router.set_route_parameter_value_decoder("tag", Box::new(codec::decode_component));
+
+        let (route_key, route_parameters) = router.parse_route("/tags/a+b").unwrap();
+        assert_eq!(route_key, Some(&"tag"));
+        assert_eq!(route_parameters["query"], "a+b");
+
+        // and the decoder for a parameter of a route has precedence over both
+        router.set_route_named_parameter_value_decoder(
+            "search",
+            "page",
+            Box::new(|value| match value.parse::<u32>() {
+                Ok(_) => Ok(Cow::Borrowed(value)),
+                Err(error) => Err(error.into()),
+            }),
+        );
+
+        let (route_key, route_parameters) = router.parse_route("/search/a+b/2").unwrap();
+        assert_eq!(route_key, Some(&"search"));
+        assert_eq!(route_parameters["query"], "a b");
+        assert_eq!(route_parameters["page"], "2");
+
+        assert_eq!(
+            router.parse_route("/search/a+b/x"),
+            Err(ParseError::InvalidParameterEncoding {
+                name: "page",
+                value: "x".to_owned(),
+            })
+        );
+
+        // the encoder for a parameter of a route has precedence over the route encoder
+        router.set_route_named_parameter_value_encoder(
+            "file",
+            "path",
+            Box::new(codec::encode_component),
+        );
+        let route_parameters = vec![("path", "a b/c")].into_iter().collect();
+        let path = router.stringify_route(&"file", &route_parameters).unwrap();
+        assert_eq!(path, "/files/a%20b%2Fc/raw");
+    }
+
+    #[test]
+    fn router_insert_route_with_codecs() {
+        let mut router = Router::new();
+
+        router
+            .set_named_parameter_value_encoder("query", Box::new(codec::encode_component))
+            .insert_route_with_codecs(
+                "search",
+                "/search/{query}",
+                Box::new(codec::encode_form),
+                Box::new(codec::decode_form),
+            )
+            .insert_route("tag", "/tags/{query}");
+
+        let route_parameters = vec![("query", "a b")].into_iter().collect();
+        let path = router
+            .stringify_route(&"search", &route_parameters)
+            .unwrap();
+        assert_eq!(path, "/search/a+b");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"search"));
+        assert_eq!(route_parameters["query"], "a b");
+
+        // the codecs only apply to the route they were inserted with
+        let route_parameters = vec![("query", "a b")].into_iter().collect();
+        let path = router.stringify_route(&"tag", &route_parameters).unwrap();
+        assert_eq!(path, "/tags/a%20b");
+
+        let (route_key, route_parameters) = router.parse_route("/tags/a+b").unwrap();
+        assert_eq!(route_key, Some(&"tag"));
+        assert_eq!(route_parameters["query"], "a+b");
+    }
+
+    #[test]
+    fn router_template_operators() {
+        let mut router = Router::new();
+
+        router
+            .set_template_operators(true)
+            .insert_route("file", "/files{+path}")
+            .insert_route("tree", "/tree{/segments*}{#line}")
+            .insert_route("search", "/search{?query,page}")
+            .insert_route("user", "/users{/id}");
+
+        let route_parameters = vec![("path", "/a b/c")].into_iter().collect();
+        let path = router.stringify_route(&"file", &route_parameters).unwrap();
+        assert_eq!(path, "/files/a%20b/c");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["path"], "/a b/c");
+
+        let route_parameters = vec![("segments", "src/lib.rs"), ("line", "L1")]
+            .into_iter()
+            .collect();
+        let path = router.stringify_route(&"tree", &route_parameters).unwrap();
+        assert_eq!(path, "/tree/src/lib.rs#L1");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"tree"));
+        assert_eq!(route_parameters["segments"], "src/lib.rs");
+        assert_eq!(route_parameters["line"], "L1");
+
+        let route_parameters = vec![("query", "a&b"), ("page", "2")].into_iter().collect();
+        let path = router
+            .stringify_route(&"search", &route_parameters)
+            .unwrap();
+        assert_eq!(path, "/search?query=a%26b&page=2");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"search"));
+        assert_eq!(route_parameters["query"], "a&b");
+        assert_eq!(route_parameters["page"], "2");
+
+        let route_parameters = vec![("id", "a/b")].into_iter().collect();
+        let path = router.stringify_route(&"user", &route_parameters).unwrap();
+        assert_eq!(path, "/users/a%2Fb");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"user"));
+        assert_eq!(route_parameters["id"], "a/b");
+
+        // the reconstructed templates only keep the operators that change the encoding
+        assert_eq!(
+            router.template_of(&"file"),
+            Some("/files{+path}".to_owned())
+        );
+        assert_eq!(
+            router.template_of(&"tree"),
+            Some("/tree{/segments*}#{+line}".to_owned())
+        );
+        assert_eq!(
+            router.template_of(&"search"),
+            Some("/search?query={query}&page={page}".to_owned())
+        );
+        assert_eq!(router.template_of(&"user"), Some("/users/{id}".to_owned()));
+        assert_eq!(
+            router.routes()[0],
+            (&"file", "/files{+path}".to_owned(), vec!["path"])
+        );
+
+        // a reconstructed template results in the same route
+        let route_parameters = vec![
+            ("path", "/a b/c"),
+            ("segments", "src/lib.rs"),
+            ("line", "L1"),
+            ("query", "a&b"),
+            ("page", "2"),
+            ("id", "a/b"),
+        ]
+        .into_iter()
+        .collect();
+        for (route_key, template, _route_parameter_names) in router.routes() {
+            let mut reconstructed_router = Router::new();
+            reconstructed_router
+                .set_template_operators(true)
+                .insert_route(*route_key, &template);
+            assert_eq!(
+                reconstructed_router.template_of(route_key),
+                Some(template.clone())
+            );
+            assert_eq!(
+                reconstructed_router.stringify_route(route_key, &route_parameters),
+                router.stringify_route(route_key, &route_parameters),
+                "{}",
+                template
+            );
+        }
+
+        // mounted templates are reconstructed from the trie too
+        let mut mounted_router = Router::new();
+        mounted_router
+            .set_template_operators(true)
+            .insert_route("file", "/files{+path}");
+        let mut router = Router::new();
+        router
+            .set_template_operators(true)
+            .mount("/api{/version}", mounted_router)
+            .unwrap();
+        assert_eq!(
+            router.template_of(&"file"),
+            Some("/api/{version}/files{+path}".to_owned())
+        );
+
+        // without operators the expression is the name of the parameter
+        let mut router = Router::new();
+        router.insert_route("file", "/files/{+path}");
+
+        let (route_key, route_parameters) = router.parse_route("/files/a").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["+path"], "a");
+    }
+
+    #[test]
+    fn router_unicode_templates() {
+        let templates = [
+            "/café/{x}/über",
+            "/café/{x}",
+            "/cafè/{x}",
+            "/ü{x}ö",
+            "/ü{x}ä",
+            "/😀/{x}/{y}",
+            "/😁",
+        ];
+
+        for templates in templates.iter().permutations(templates.len()).take(100) {
+            let mut router = Router::new();
+            for template in templates.into_iter().cloned() {
+                router.insert_route(template, template);
+            }
+
+            let (route_key, route_parameters) = router.parse_route("/café/1/über").unwrap();
+            assert_eq!(route_key, Some(&"/café/{x}/über"));
+            assert_eq!(route_parameters["x"], "1");
+
+            let (route_key, route_parameters) = router.parse_route("/café/1").unwrap();
+            assert_eq!(route_key, Some(&"/café/{x}"));
+            assert_eq!(route_parameters["x"], "1");
+
+            let (route_key, route_parameters) = router.parse_route("/cafè/2").unwrap();
+            assert_eq!(route_key, Some(&"/cafè/{x}"));
+            assert_eq!(route_parameters["x"], "2");
+
+            let (route_key, route_parameters) = router.parse_route("/üaä").unwrap();
+            assert_eq!(route_key, Some(&"/ü{x}ä"));
+            assert_eq!(route_parameters["x"], "a");
+
+            let (route_key, route_parameters) = router.parse_route("/😀/a/b").unwrap();
+            assert_eq!(route_key, Some(&"/😀/{x}/{y}"));
+            assert_eq!(route_parameters["y"], "b");
+
+            assert_eq!(router.parse_route("/😁").unwrap().0, Some(&"/😁"));
+            assert_eq!(router.parse_route("/😂").unwrap().0, None);
+
+            let route_parameters = vec![("x", "é")].into_iter().collect();
+            assert_eq!(
+                router
+                    .stringify_route(&"/café/{x}/über", &route_parameters)
+                    .unwrap(),
+                "/café/%C3%A9/über"
+            );
+        }
+
+        // the window for the anchor must not end in the middle of a char
+        let mut router = Router::new();
+        router
+            .set_maximum_parameter_value_length(2)
+            .insert_route("a", "/{x}/a")
+            .insert_route("b", "/{x}é");
+
+        assert_eq!(router.parse_route("/éé/a").unwrap().0, None);
+        assert_eq!(router.parse_route("/ééé").unwrap().0, None);
+        assert_eq!(router.parse_route("/aé").unwrap().0, Some(&"b"));
+    }
+
+    #[test]
+    fn router_parameter_value_match() {
+        let mut router = Router::new();
+        router
+            .insert_route("json", "/files/{name}.json")
+            .insert_route("file", "/files/{name}.{extension}/raw");
+
+        // the first occurrence of the anchor is not followed by the rest of a route
+        assert_eq!(router.parse_route("/files/a.json.json").unwrap().0, None);
+
+        router.set_parameter_value_match(ParameterValueMatch::Shortest);
+
+        let (route_key, route_parameters) = router.parse_route("/files/a.json.json").unwrap();
+        assert_eq!(route_key, Some(&"json"));
+        assert_eq!(route_parameters["name"], "a.json");
+
+        let (route_key, route_parameters) = router.parse_route("/files/a.b.c/raw").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["name"], "a");
+        assert_eq!(route_parameters["extension"], "b.c");
+
+        router.set_parameter_value_match(ParameterValueMatch::Longest);
+
+        let (route_key, route_parameters) = router.parse_route("/files/a.b.c/raw").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["name"], "a.b");
+        assert_eq!(route_parameters["extension"], "c");
+
+        // occurrences outside of the window are not tried
+        router.set_maximum_parameter_value_length(3);
+        assert_eq!(
+            router.parse_route("/files/a.b.c/raw").unwrap().0,
+            Some(&"file")
+        );
+        assert_eq!(router.parse_route("/files/a.b.json.json").unwrap().0, None);
+    }
+
+    #[test]
+    fn router_parameter_value_match_adversarial() {
+        let mut router = Router::new();
+        router
+            .set_maximum_parameter_value_length(200)
+            .insert_route("z", "/{a}.{b}.{c}.{d}.{e}.{f}/z");
+
+        // every combination of dots would be tried if the nodes did not remember what they
+        // already tried, this would take ages
+        let path = format!("/{}", ".".repeat(120));
+
+        for parameter_value_match in [ParameterValueMatch::Shortest, ParameterValueMatch::Longest] {
+            router.set_parameter_value_match(parameter_value_match);
+
+            assert_eq!(router.parse_route(&path).unwrap().0, None);
+
+            let (route_key, _route_parameters, trace) = router.parse_route_traced(&path).unwrap();
+            assert_eq!(route_key, None);
+            assert!(trace
+                .steps
+                .iter()
+                .any(|step| step.outcome == ParseTraceOutcome::AlreadyTried));
+
+            let (route_key, route_parameters) = router.parse_route("/a.b.c.d.e.f/z").unwrap();
+            assert_eq!(route_key, Some(&"z"));
+            assert_eq!(route_parameters["f"], "f");
+        }
+    }
+
+    #[test]
+    fn router_parameter_value_separators() {
+        let mut router = Router::new();
+        router
+            .set_parameter_value_separators("/")
+            .insert_route("c", "/c/{x}")
+            .insert_route("d", "/d/{x}.json");
+
+        let (route_key, route_parameters) = router.parse_route("/c/3").unwrap();
+        assert_eq!(route_key, Some(&"c"));
+        assert_eq!(route_parameters["x"], "3");
+
+        assert_eq!(router.parse_route("/c/3/4").unwrap().0, None);
+        assert_eq!(router.parse_route("/d/3/4.json").unwrap().0, None);
+        assert_eq!(router.parse_route("/d/3.json").unwrap().0, Some(&"d"));
+
+        // a parameter with separators is tried before one without, so both can be in a router
+        let mut router = Router::new();
+        router
+            .set_named_parameter_value_separators("id", "/")
+            .insert_route("file", "/files/{id}")
+            .insert_route("path", "/files/{path}");
+
+        let (route_key, route_parameters) = router.parse_route("/files/3").unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["id"], "3");
+
+        let (route_key, route_parameters) = router.parse_route("/files/3/4").unwrap();
+        assert_eq!(route_key, Some(&"path"));
+        assert_eq!(route_parameters["path"], "3/4");
+
+        // separators are not checked in the encoded value
+        let route_parameters = vec![("id", "3/4")].into_iter().collect();
+        let path = router.stringify_route(&"file", &route_parameters).unwrap();
+        assert_eq!(path, "/files/3%2F4");
+
+        let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+        assert_eq!(route_key, Some(&"file"));
+        assert_eq!(route_parameters["id"], "3/4");
+    }
+
+    #[test]
+    fn router_priority() {
+        let mut router = Router::new();
+        router
+            .insert_route("all", "/product/all")
+            .insert_route("product", "/product/{id}");
+
+        assert_eq!(router.parse_route("/product/all").unwrap().0, Some(&"all"));
+
+        // the parameter wins over the literal
+        let mut router = Router::new();
+        router
+            .insert_route("all", "/product/all")
+            .insert_route_with_priority("product", "/product/{id}", 1);
+
+        assert_eq!(
+            router.parse_route("/product/all").unwrap().0,
+            Some(&"product")
+        );
+        assert_eq!(
+            router.parse_route("/product/1").unwrap().0,
+            Some(&"product")
+        );
+
+        // the order of insertion does not matter, and the priority of a route also applies to
+        // the nodes it shares with other routes
+        let templates = [
+            ("a", "/a/{x}/b", 0),
+            ("b", "/a/{x}/{y}", 2),
+            ("c", "/a/c/{y}", 1),
+            ("d", "/a/c/d", 0),
+        ];
+        for templates in templates.iter().permutations(templates.len()) {
+            let mut router = Router::new();
+            for (route_key, template, priority) in templates.iter().cloned() {
+                router.insert_route_with_priority(*route_key, template, *priority);
+            }
+
+            assert_eq!(router.parse_route("/a/x/b").unwrap().0, Some(&"b"));
+            assert_eq!(router.parse_route("/a/c/d").unwrap().0, Some(&"b"));
+
+            // without the route with the highest priority, the next one wins
+            let mut router = Router::new();
+            for (route_key, template, priority) in templates.iter().cloned() {
+                if *route_key != "b" {
+                    router.insert_route_with_priority(*route_key, template, *priority);
+                }
+            }
+
+            assert_eq!(router.parse_route("/a/x/b").unwrap().0, Some(&"a"));
+            assert_eq!(router.parse_route("/a/c/d").unwrap().0, Some(&"c"));
+        }
+    }
+
+    #[test]
+    fn router_metadata() {
+        #[derive(Debug, PartialEq)]
+        struct RouteMeta {
+            handler: &'static str,
+            authenticated: bool,
+        }
+
+        let mut router = Router::new_with_metadata();
+        router
+            .insert_route_with_metadata(
+                "product",
+                "/product/{id}",
+                RouteMeta {
+                    handler: "get_product",
+                    authenticated: false,
+                },
+            )
+            .insert_route_with_priority_and_metadata(
+                "all",
+                "/product/{id}/all",
+                1,
+                RouteMeta {
+                    handler: "get_products",
+                    authenticated: true,
+                },
+            )
+            .insert_route("home", "/");
+
+        let (route_key, route_metadata, route_parameters) =
+            router.parse_route_with_metadata("/product/1").unwrap();
+        assert_eq!(route_key, Some(&"product"));
+        assert_eq!(route_metadata.unwrap().handler, "get_product");
+        assert_eq!(route_parameters.get("id").unwrap(), "1");
+
+        let (route_key, route_metadata, _route_parameters) =
+            router.parse_route_with_metadata("/product/1/all").unwrap();
+        assert_eq!(route_key, Some(&"all"));
+        assert!(route_metadata.unwrap().authenticated);
+
+        let (route_key, route_metadata, _route_parameters) =
+            router.parse_route_with_metadata("/").unwrap();
+        assert_eq!(route_key, Some(&"home"));
+        assert_eq!(route_metadata, None);
+
+        let (route_key, route_metadata, _route_parameters) =
+            router.parse_route_with_metadata("/not-found").unwrap();
+        assert_eq!(route_key, None);
+        assert_eq!(route_metadata, None);
+
+        // inserting a route again replaces the metadata
+        router.insert_route_with_metadata(
+            "product",
+            "/product/{id}",
+            RouteMeta {
+                handler: "get_product_v2",
+                authenticated: false,
+            },
+        );
+        assert_eq!(
+            router.route_metadata(&"product").unwrap().handler,
+            "get_product_v2"
+        );
+        assert_eq!(router.route_metadata(&"home"), None);
+        assert_eq!(router.route_metadata(&"unknown"), None);
+    }
+
+    #[test]
+    fn router_mount() {
+        let mut users_router = Router::new_with_metadata();
+        users_router
+            .set_parameter_value_separators("/")
+            .insert_route_with_metadata("list", "/users", "list_users")
+            .insert_route_with_priority_and_metadata("detail", "/users/{id}", 1, "get_user")
+            .insert_route("search", "/users/search");
+
+        let mut router = Router::new_with_metadata();
+        router
+            .insert_route_with_metadata(("api", "home"), "/", "home")
+            .mount_with_key_mapping("/api/{version}", users_router, |route_key| {
+                ("users", route_key)
+            })
+            .unwrap();
+
+        let (route_key, route_metadata, route_parameters) =
+            router.parse_route_with_metadata("/api/v1/users").unwrap();
+        assert_eq!(route_key, Some(&("users", "list")));
+        assert_eq!(route_metadata, Some(&"list_users"));
+        assert_eq!(route_parameters.get("version").unwrap(), "v1");
+
+        // the priority of the mounted route is kept
+        let (route_key, route_metadata, route_parameters) = router
+            .parse_route_with_metadata("/api/v2/users/search")
+            .unwrap();
+        assert_eq!(route_key, Some(&("users", "detail")));
+        assert_eq!(route_metadata, Some(&"get_user"));
+        assert_eq!(route_parameters.get("version").unwrap(), "v2");
+        assert_eq!(route_parameters.get("id").unwrap(), "search");
+
+        // the separators of the mounted route are kept
+        let (route_key, _route_parameters) = router.parse_route("/api/v2/users/1/2").unwrap();
+        assert_eq!(route_key, None);
+
+        let (route_key, route_metadata, _route_parameters) =
+            router.parse_route_with_metadata("/").unwrap();
+        assert_eq!(route_key, Some(&("api", "home")));
+        assert_eq!(route_metadata, Some(&"home"));
 
-        Self {
-            root_node_rc: RouteNodeRc::default(),
-            leaf_nodes_rc: HashMap::new(),
-            maximum_parameter_value_length: 20,
-            parameter_placeholder_re: &TEMPLATE_PLACEHOLDER_REGEX,
-            parameter_value_encoder,
-            parameter_value_decoder,
-        }
-    }
+        let mut route_parameters = HashMap::new();
+        route_parameters.insert("version", "v1");
+        route_parameters.insert("id", "a b");
+        assert_eq!(
+            router
+                .stringify_route(&("users", "detail"), &route_parameters)
+                .unwrap(),
+            "/api/v1/users/a%20b"
+        );
 
-    pub fn set_maximum_parameter_value_length(&mut self, value: usize) -> &mut Self {
-        self.maximum_parameter_value_length = value;
+        // mounting under a literal prefix, with the same type of key
+        let mut admin_router = Router::new();
+        admin_router
+            .insert_route("admin-root", "/")
+            .insert_route("admin-user", "{id}")
+            .set_route_parameter_value_decoder(
+                "admin-user",
+                Box::new(|value| Ok(Cow::Owned(value.to_uppercase()))),
+            );
 
-        self
+        let mut router = Router::new();
+        router
+            .insert_route("home", "/")
+            .mount("/admin", admin_router)
+            .unwrap();
+
+        assert_eq!(
+            router.parse_route("/admin/").unwrap().0,
+            Some(&"admin-root")
+        );
+        let (route_key, route_parameters) = router.parse_route("/adminx").unwrap();
+        assert_eq!(route_key, Some(&"admin-user"));
+        assert_eq!(route_parameters.get("id").unwrap(), "X");
+        assert_eq!(router.parse_route("/").unwrap().0, Some(&"home"));
     }
 
-    pub fn set_parameter_placeholder_re(&mut self, value: &'r Regex) -> &mut Self {
-        self.parameter_placeholder_re = value;
+    #[test]
+    fn router_mount_conflicts() {
+        let mut router = Router::new();
+        router
+            .insert_route("home", "/")
+            .insert_route("user", "/api/users/{id}");
 
-        self
-    }
+        // nothing is mounted when one of the routes conflicts
+        let mut api_router = Router::new();
+        api_router
+            .insert_route("users", "/users")
+            .insert_route("other-user", "/users/{name}");
+        assert_eq!(
+            router.mount("/api", api_router).err(),
+            Some(MergeConflict::AmbiguousRoute {
+                route_key: "user",
+                other_route_key: "other-user",
+                other_template: "/api/users/{name}".to_owned(),
+            })
+        );
+        assert_eq!(router.len(), 2);
+        assert!(!router.contains_route(&"users"));
+        assert_eq!(router.parse_route("/api/users").unwrap().0, None);
+        assert_eq!(router.parse_route("/api/users/1").unwrap().0, Some(&"user"));
 
-    pub fn set_parameter_value_encoder(&mut self, value: Box<ParameterValueEncoder>) -> &mut Self {
-        self.parameter_value_encoder = value;
+        let mut api_router = Router::new();
+        api_router.insert_route("home", "/home");
+        assert_eq!(
+            router.mount("/api", api_router).err(),
+            Some(MergeConflict::DuplicateRouteKey {
+                route_key: "home",
+                template: "/".to_owned(),
+                other_template: "/api/home".to_owned(),
+            })
+        );
 
-        self
+        // the parameter of the prefix would match the rest of the path
+        let mut api_router = Router::new();
+        api_router
+            .insert_route("file", "/files")
+            .insert_route("format", "{format}/raw");
+        assert_eq!(
+            router.mount("/api/{version}", api_router).err(),
+            Some(MergeConflict::AdjacentParameters {
+                route_key: "format",
+                template: "/api/{version}{format}/raw".to_owned(),
+            })
+        );
+        assert_eq!(router.len(), 2);
+        assert_eq!(router.to_tree_string(), {
+            let mut expected_router = Router::new();
+            expected_router
+                .insert_route("home", "/")
+                .insert_route("user", "/api/users/{id}");
+            expected_router.to_tree_string()
+        });
     }
 
-    pub fn set_parameter_value_decoder(&mut self, value: Box<ParameterValueDecoder>) -> &mut Self {
-        self.parameter_value_decoder = value;
+    #[test]
+    fn router_mount_named_codecs() {
+        let mut search_router = Router::new();
+        search_router
+            .insert_route("search", "/search/{query}")
+            .insert_route("tag", "/tags/{query}")
+            .set_named_parameter_value_encoder("query", Box::new(codec::encode_form))
+            .set_named_parameter_value_decoder("query", Box::new(codec::decode_form))
+            .set_route_parameter_value_decoder("tag", Box::new(codec::decode_component));
 
-        self
-    }
+        let mut router = Router::new();
+        router
+            .insert_route("query", "/query/{query}")
+            .mount("/api", search_router)
+            .unwrap();
 
-    pub fn insert_route(&mut self, route_key: K, template: &'r str) -> &mut Self {
-        let leaf_node_rc = route_node_insert(
-            self.root_node_rc.clone(),
-            route_key,
-            template,
-            self.parameter_placeholder_re,
+        let route_parameters = vec![("query", "a b")].into_iter().collect();
+        assert_eq!(
+            router
+                .stringify_route(&"search", &route_parameters)
+                .unwrap(),
+            "/api/search/a+b"
         );
-        self.leaf_nodes_rc.insert(route_key, leaf_node_rc);
+        let (route_key, route_parameters) = router.parse_route("/api/search/a+b").unwrap();
+        assert_eq!(route_key, Some(&"search"));
+        assert_eq!(route_parameters["query"], "a b");
 
-        self
+        // the decoder of the route still has precedence
+        let (route_key, route_parameters) = router.parse_route("/api/tags/a+b").unwrap();
+        assert_eq!(route_key, Some(&"tag"));
+        assert_eq!(route_parameters["query"], "a+b");
+
+        // and the codecs do not apply to the routes of this router
+        let route_parameters = vec![("query", "a b")].into_iter().collect();
+        assert_eq!(
+            router.stringify_route(&"query", &route_parameters).unwrap(),
+            "/query/a%20b"
+        );
+        let (route_key, route_parameters) = router.parse_route("/query/a+b").unwrap();
+        assert_eq!(route_key, Some(&"query"));
+        assert_eq!(route_parameters["query"], "a+b");
     }
 
-    pub fn parse_route<'f>(&self, path: &'f str) -> (Option<K>, HashMap<&'r str, Cow<'f, str>>) {
-        let (route_key, parameter_names, parameter_values) = route_node_parse(
-            self.root_node_rc.clone(),
-            path,
-            self.maximum_parameter_value_length,
+    #[test]
+    fn router_merge() {
+        let mut router = Router::new_with_metadata();
+        router
+            .insert_route_with_metadata("home", "/", "home")
+            .insert_route_with_metadata("product", "/product/{id}", "get_product")
+            .insert_route("category", "/category/{id}");
+
+        let mut other_router = Router::new_with_metadata();
+        other_router
+            .insert_route("home", "/")
+            .insert_route_with_metadata("reviews", "/product/{id}/reviews", "get_reviews")
+            .insert_route("category", "/categories/{id}")
+            .insert_route("product-by-name", "/product/{name}")
+            .set_route_parameter_value_decoder(
+                "reviews",
+                Box::new(|value| Ok(Cow::Owned(value.to_uppercase()))),
+            );
+
+        let mut conflicts = router.merge(other_router);
+        conflicts.sort_by_key(|conflict| conflict.to_string());
+        assert_eq!(
+            conflicts,
+            vec![
+                MergeConflict::DuplicateRouteKey {
+                    route_key: "category",
+                    template: "/category/{id}".to_owned(),
+                    other_template: "/categories/{id}".to_owned(),
+                },
+                MergeConflict::AmbiguousRoute {
+                    route_key: "product",
+                    other_route_key: "product-by-name",
+                    other_template: "/product/{name}".to_owned(),
+                },
+            ]
         );
 
-        if let Some(route_key) = route_key {
-            let parameters: HashMap<_, _> = parameter_names
-                .iter()
-                .cloned()
-                .zip(
-                    parameter_values
-                        .iter()
-                        .map(|parameter_value| (self.parameter_value_decoder)(parameter_value)),
-                )
-                .collect();
+        // the route that was in both routers is kept
+        let (route_key, route_metadata, _route_parameters) =
+            router.parse_route_with_metadata("/").unwrap();
+        assert_eq!(route_key, Some(&"home"));
+        assert_eq!(route_metadata, Some(&"home"));
 
-            (Some(route_key), parameters)
-        } else {
-            Default::default()
-        }
+        let (route_key, route_metadata, route_parameters) = router
+            .parse_route_with_metadata("/product/a/reviews")
+            .unwrap();
+        assert_eq!(route_key, Some(&"reviews"));
+        assert_eq!(route_metadata, Some(&"get_reviews"));
+        assert_eq!(route_parameters.get("id").unwrap(), "A");
+
+        assert_eq!(
+            router.parse_route("/product/a").unwrap().0,
+            Some(&"product")
+        );
+        assert_eq!(router.parse_route("/categories/a").unwrap().0, None);
     }
 
-    pub fn stringify_route<'f>(
-        &self,
-        route_key: K,
-        route_parameters: &'f HashMap<&'f str, &'f str>,
-    ) -> Option<Cow<'f, str>>
-    where
-        'r: 'f,
-    {
-        if let Some(node_rc) = self.leaf_nodes_rc.get(&route_key) {
-            let parameter_values: Vec<_> = node_rc
-                .borrow()
-                .route_parameter_names
-                .iter()
-                .map(|parameter_name| route_parameters.get(parameter_name).unwrap())
-                .map(|parameter_value| (self.parameter_value_encoder)(parameter_value))
-                .collect();
+    #[test]
+    fn router_diff() {
+        let mut router = Router::new();
+        router
+            .insert_route("home", "/")
+            .insert_route("product", "/product/{id}")
+            .insert_route("category", "/category/{id}");
 
-            Some(route_node_stringify(node_rc.clone(), parameter_values))
-        } else {
-            None
-        }
-    }
-}
+        let mut other_router = Router::new();
+        other_router
+            .insert_route("home", "/")
+            .insert_route("product", "/product/{name}")
+            .insert_route("reviews", "/product/{id}/reviews");
 
-impl<'r, K: Eq + Hash + Copy> Default for Router<'r, K> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        let diff = router.diff(&other_router);
+        assert_eq!(
+            diff,
+            RouterDiff {
+                added: vec![("reviews", "/product/{id}/reviews".to_owned())],
+                removed: vec![("category", "/category/{id}".to_owned())],
+                changed: vec![(
+                    "product",
+                    "/product/{id}".to_owned(),
+                    "/product/{name}".to_owned()
+                )],
+            }
+        );
+        assert!(!diff.is_empty());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashSet;
+        assert!(router.diff(&router).is_empty());
+    }
 
     #[test]
-    fn readme() {
+    fn router_routes() {
         let mut router = Router::new();
+        assert!(router.is_empty());
 
         router
-            .insert_route("all-products", "/product/all")
-            .insert_route("product-detail", "/product/{id}");
+            .insert_route("product", "/product/{id}")
+            .insert_route("home", "/")
+            .insert_route("reviews", "/product/{id}/reviews/{page}");
 
-        // And now we can parse routes!
+        assert_eq!(router.len(), 3);
+        assert!(!router.is_empty());
+        assert!(router.contains_route(&"home"));
+        assert!(!router.contains_route(&"category"));
+        assert_eq!(
+            router.template_of(&"reviews"),
+            Some("/product/{id}/reviews/{page}".to_owned())
+        );
+        assert_eq!(router.template_of(&"category"), None);
+        assert_eq!(
+            router.routes(),
+            vec![
+                (&"home", "/".to_owned(), vec![]),
+                (&"product", "/product/{id}".to_owned(), vec!["id"]),
+                (
+                    &"reviews",
+                    "/product/{id}/reviews/{page}".to_owned(),
+                    vec!["id", "page"]
+                ),
+            ]
+        );
 
-        {
-            let (route_key, route_parameters) = router.parse_route("/not-found");
-            assert_eq!(route_key, None);
-            assert_eq!(route_parameters, Default::default());
-        }
+        // inserting a route again with another template replaces the route
+        router.insert_route("product", "/products/{name}");
+        assert_eq!(router.len(), 3);
+        assert_eq!(
+            router.template_of(&"product"),
+            Some("/products/{name}".to_owned())
+        );
+        assert_eq!(router.parse_route("/product/1").unwrap().0, None);
+        assert_eq!(
+            router.parse_route("/products/1").unwrap().0,
+            Some(&"product")
+        );
+    }
 
-        {
-            let (route_key, route_parameters) = router.parse_route("/product/all");
-            assert_eq!(route_key, Some("all-products"));
-            assert_eq!(route_parameters, Default::default());
-        }
+    #[test]
+    fn router_owned_keys() {
+        let route_keys: Vec<String> = vec!["product".to_owned(), "reviews".to_owned()];
+
+        let mut router = Router::new();
+        router
+            .insert_route(route_keys[0].clone(), "/product/{id}")
+            .insert_route(route_keys[1].clone(), "/product/{id}/reviews")
+            .set_route_parameter_value_decoder(
+                route_keys[1].clone(),
+                Box::new(|value| Ok(Cow::Owned(value.to_uppercase()))),
+            );
+
+        let (route_key, route_parameters) = router.parse_route("/product/a/reviews").unwrap();
+        assert_eq!(route_key, Some(&route_keys[1]));
+        assert_eq!(route_parameters.get("id").unwrap(), "A");
+
+        let mut route_parameters = HashMap::new();
+        route_parameters.insert("id", "1");
+        assert_eq!(
+            router
+                .stringify_route(&route_keys[0], &route_parameters)
+                .unwrap(),
+            "/product/1"
+        );
+        assert_eq!(
+            router.template_of(&"reviews".to_owned()),
+            Some("/product/{id}/reviews".to_owned())
+        );
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn router_build_url() {
+        let mut router = Router::new();
+        router
+            .insert_route("home", "/")
+            .insert_route("product", "/product/{id}")
+            .insert_route("relative", "product/{id}/reviews");
+
+        let mut route_parameters = HashMap::new();
+        route_parameters.insert("id", "a b/c");
+
+        for base_url in [
+            "https://example.com:8080/api",
+            "https://example.com:8080/api/",
+        ] {
+            let base_url = url::Url::parse(base_url).unwrap();
 
-        {
-            let (route_key, route_parameters) = router.parse_route("/product/1");
-            assert_eq!(route_key, Some("product-detail"));
             assert_eq!(
-                route_parameters,
-                vec![("id", "1")]
-                    .into_iter()
-                    .map(|(k, v)| (k, Cow::Borrowed(v)))
-                    .collect()
+                router
+                    .build_url(&"product", &route_parameters, &base_url)
+                    .unwrap()
+                    .as_str(),
+                "https://example.com:8080/api/product/a%20b%2Fc"
+            );
+            assert_eq!(
+                router
+                    .build_url(&"relative", &route_parameters, &base_url)
+                    .unwrap()
+                    .as_str(),
+                "https://example.com:8080/api/product/a%20b%2Fc/reviews"
+            );
+            assert_eq!(
+                router
+                    .build_url(&"home", &route_parameters, &base_url)
+                    .unwrap()
+                    .as_str(),
+                "https://example.com:8080/api/"
             );
         }
 
-        // And we can stringify routes
+        let base_url = url::Url::parse("http://example.com?page=1#top").unwrap();
+        assert_eq!(
+            router
+                .build_url(&"product", &route_parameters, &base_url)
+                .unwrap()
+                .as_str(),
+            "http://example.com/product/a%20b%2Fc"
+        );
+        assert_eq!(
+            router
+                .build_url_with_query(
+                    &"product",
+                    &route_parameters,
+                    &base_url,
+                    &[("page", "2"), ("q", "a&b")],
+                    Some("reviews"),
+                )
+                .unwrap()
+                .as_str(),
+            "http://example.com/product/a%20b%2Fc?page=2&q=a%26b#reviews"
+        );
 
-        {
-            let route_parameters: HashMap<_, _> = vec![].into_iter().collect();
-            let path = router.stringify_route("all-products", &route_parameters);
-            assert_eq!(path.unwrap().into_owned(), "/product/all".to_owned());
-        }
+        // the query and the fragment of a template with operators are not part of the path
+        let mut router = Router::new();
+        router
+            .set_template_operators(true)
+            .insert_route("search", "/search{?q}")
+            .insert_route("tree", "/tree{/segments*}{#line}");
 
-        {
-            let route_parameters: HashMap<_, _> = vec![("id", "2")].into_iter().collect();
-            let path = router.stringify_route("product-detail", &route_parameters);
-            assert_eq!(path.unwrap().into_owned(), "/product/2".to_owned());
-        }
+        let route_parameters = vec![("q", "x y"), ("segments", "a/b"), ("line", "L1")]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            router
+                .build_url(&"search", &route_parameters, &base_url)
+                .unwrap()
+                .as_str(),
+            "http://example.com/search?q=x%20y"
+        );
+        assert_eq!(
+            router
+                .build_url_with_query(
+                    &"search",
+                    &route_parameters,
+                    &base_url,
+                    &[("page", "2")],
+                    None,
+                )
+                .unwrap()
+                .as_str(),
+            "http://example.com/search?q=x%20y&page=2"
+        );
+        assert_eq!(
+            router
+                .build_url(&"tree", &route_parameters, &base_url)
+                .unwrap()
+                .as_str(),
+            "http://example.com/tree/a/b#L1"
+        );
+        assert_eq!(
+            router
+                .build_url_with_query(
+                    &"tree",
+                    &route_parameters,
+                    &base_url,
+                    &[("page", "2")],
+                    Some("L2"),
+                )
+                .unwrap()
+                .as_str(),
+            "http://example.com/tree/a/b?page=2#L2"
+        );
+
+        assert_eq!(
+            router.build_url(&"unknown", &route_parameters, &base_url),
+            None
+        );
+        let base_url = url::Url::parse("mailto:someone@example.com").unwrap();
+        assert_eq!(
+            router.build_url(&"product", &route_parameters, &base_url),
+            None
+        );
+    }
+
+    #[test]
+    fn router_stringify_relative() {
+        let mut router = Router::new();
+        router
+            .insert_route("home", "/")
+            .insert_route("product", "/product/{id}")
+            .insert_route("reviews", "/product/{id}/reviews");
+
+        let mut route_parameters = HashMap::new();
+        route_parameters.insert("id", "2");
+
+        assert_eq!(
+            router.stringify_relative("/product/1/reviews", &"product", &route_parameters),
+            Ok("../2".to_owned())
+        );
+        assert_eq!(
+            router.stringify_relative("/product/1", &"reviews", &route_parameters),
+            Ok("2/reviews".to_owned())
+        );
+        assert_eq!(
+            router.stringify_relative("/product/1/reviews", &"home", &route_parameters),
+            Ok("../../".to_owned())
+        );
+        assert_eq!(
+            router.stringify_relative("/", &"unknown", &route_parameters),
+            Err(StringifyError::UnknownRoute)
+        );
     }
 
     #[test]
     fn router_1() {
-        #[derive(Debug, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
         enum Route {
             A,
             B,
@@ -197,16 +3031,16 @@ mod tests {
 
         let mut router = Router::new();
         router
-            .insert_route(&Route::A, "/a")
-            .insert_route(&Route::B, "/b/{x}")
-            .insert_route(&Route::C, "/b/{y}/c")
-            .insert_route(&Route::D, "/b/{z}/d");
+            .insert_route(Route::A, "/a")
+            .insert_route(Route::B, "/b/{x}")
+            .insert_route(Route::C, "/b/{y}/c")
+            .insert_route(Route::D, "/b/{z}/d");
 
-        let (route_key, route_parameters) = router.parse_route("/a");
+        let (route_key, route_parameters) = router.parse_route("/a").unwrap();
         assert_eq!(route_key.unwrap(), &Route::A);
         assert_eq!(route_parameters, vec![].into_iter().collect());
 
-        let (route_key, route_parameters) = router.parse_route("/b/123");
+        let (route_key, route_parameters) = router.parse_route("/b/123").unwrap();
         assert_eq!(route_key.unwrap(), &Route::B);
         assert_eq!(
             route_parameters,
@@ -216,7 +3050,7 @@ mod tests {
                 .collect(),
         );
 
-        let (route_key, route_parameters) = router.parse_route("/b/456/c");
+        let (route_key, route_parameters) = router.parse_route("/b/456/c").unwrap();
         assert_eq!(route_key.unwrap(), &Route::C);
         assert_eq!(
             route_parameters,
@@ -226,7 +3060,7 @@ mod tests {
                 .collect(),
         );
 
-        let (route_key, route_parameters) = router.parse_route("/b/789/d");
+        let (route_key, route_parameters) = router.parse_route("/b/789/d").unwrap();
         assert_eq!(route_key.unwrap(), &Route::D);
         assert_eq!(
             route_parameters,
@@ -249,11 +3083,11 @@ mod tests {
             .insert_route("three", "/c/{x}")
             .insert_route("four", "/c/{y}/{z}/");
 
-        let (route_key, _route_parameters) = router.parse_route("/a");
-        assert_eq!(route_key.unwrap(), "one");
+        let (route_key, _route_parameters) = router.parse_route("/a").unwrap();
+        assert_eq!(*route_key.unwrap(), "one");
 
-        let (route_key, route_parameters) = router.parse_route("/a/1/2");
-        assert_eq!(route_key.unwrap(), "two");
+        let (route_key, route_parameters) = router.parse_route("/a/1/2").unwrap();
+        assert_eq!(*route_key.unwrap(), "two");
         assert_eq!(
             route_parameters,
             vec![("x", "1"), ("y", "2"),]
@@ -265,12 +3099,12 @@ mod tests {
         let route_key = "two";
         let route_parameters = vec![("x", "1"), ("y", "2")].into_iter().collect();
         let path = router
-            .stringify_route(route_key, &route_parameters)
+            .stringify_route(&route_key, &route_parameters)
             .unwrap();
         assert_eq!(path, "/a/1/2");
 
-        let (route_key, route_parameters) = router.parse_route("/c/3");
-        assert_eq!(route_key.unwrap(), "three");
+        let (route_key, route_parameters) = router.parse_route("/c/3").unwrap();
+        assert_eq!(*route_key.unwrap(), "three");
         assert_eq!(
             route_parameters,
             vec![("x", "3"),]
@@ -279,8 +3113,8 @@ mod tests {
                 .collect(),
         );
 
-        let (route_key, route_parameters) = router.parse_route("/c/3/4");
-        assert_eq!(route_key.unwrap(), "three");
+        let (route_key, route_parameters) = router.parse_route("/c/3/4").unwrap();
+        assert_eq!(*route_key.unwrap(), "three");
         assert_eq!(
             route_parameters,
             vec![("x", "3/4"),]
@@ -292,12 +3126,12 @@ mod tests {
         let route_key = "three";
         let route_parameters = vec![("x", "3/4")].into_iter().collect();
         let path = router
-            .stringify_route(route_key, &route_parameters)
+            .stringify_route(&route_key, &route_parameters)
             .unwrap();
         assert_eq!(path, "/c/3%2F4");
 
-        let (route_key, route_parameters) = router.parse_route("/c/3/4/");
-        assert_eq!(route_key.unwrap(), "four");
+        let (route_key, route_parameters) = router.parse_route("/c/3/4/").unwrap();
+        assert_eq!(*route_key.unwrap(), "four");
         assert_eq!(
             route_parameters,
             vec![("y", "3"), ("z", "4"),]
@@ -366,7 +3200,7 @@ mod tests {
             let path = &paths[index];
             let template = templates[index];
 
-            let (route_key, route_parameters) = router.parse_route(path);
+            let (route_key, route_parameters) = router.parse_route(path).unwrap();
 
             let expected_parameters: HashMap<_, _> = route_parameters
                 .keys()
@@ -374,8 +3208,262 @@ mod tests {
                 .map(|key| (key, Cow::Borrowed(all_parameters[key])))
                 .collect();
 
-            assert_eq!(route_key, Some(template));
+            assert_eq!(route_key, Some(&template));
             assert_eq!(route_parameters, expected_parameters);
         }
     }
+
+    // run the cases in `fixtures/conformance.json`. Every case has a path and either an expected
+    // route key, where null means no match, or `"error": true` if parsing should fail. If there
+    // is a route, the parameters are stringified again and should result in the path, or in
+    // `stringified` if that is present.
+    #[test]
+    fn router_conformance() {
+        let fixtures = std::fs::read_to_string("fixtures/conformance.json").unwrap();
+        let fixtures: serde_json::Value = serde_json::from_str(&fixtures).unwrap();
+
+        for fixture in fixtures.as_array().unwrap() {
+            let description = fixture["description"].as_str().unwrap();
+            let templates: Vec<_> = fixture["templates"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|template| template.as_str().unwrap())
+                .collect();
+
+            let mut router = Router::new();
+            for template in templates.iter().cloned() {
+                router.insert_route(template, template);
+            }
+
+            for case in fixture["cases"].as_array().unwrap() {
+                let path = case["path"].as_str().unwrap();
+                let message = format!("{}: {}", description, path);
+
+                let result = router.parse_route(path);
+                if case["error"].as_bool().unwrap_or(false) {
+                    assert!(result.is_err(), "{}", message);
+                    continue;
+                }
+
+                let (route_key, route_parameters) = result.expect(&message);
+                assert_eq!(route_key.cloned(), case["key"].as_str(), "{}", message);
+
+                let route_key = match route_key {
+                    Some(route_key) => route_key,
+                    None => continue,
+                };
+
+                let expected_parameters: HashMap<_, _> = case["parameters"]
+                    .as_object()
+                    .unwrap()
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str().unwrap()))
+                    .collect();
+                let actual_parameters: HashMap<_, _> = route_parameters
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_ref()))
+                    .collect();
+                assert_eq!(actual_parameters, expected_parameters, "{}", message);
+
+                let stringified = case["stringified"].as_str().unwrap_or(path);
+                assert_eq!(
+                    router
+                        .stringify_route(route_key, &actual_parameters)
+                        .unwrap(),
+                    stringified,
+                    "{}",
+                    message
+                );
+            }
+        }
+    }
+
+    // a template of one to four segments, every segment is a literal or a parameter. Parameters
+    // are named after their position so equal templates are the only ambiguous ones.
+    fn template_strategy() -> impl proptest::strategy::Strategy<Value = String> {
+        use proptest::prelude::*;
+
+        (
+            proptest::collection::vec(proptest::option::of("[a-cé😀]{1,3}"), 1..5),
+            any::<bool>(),
+        )
+            .prop_map(|(segments, trailing_slash)| {
+                let mut template = String::new();
+                for (index, segment) in segments.into_iter().enumerate() {
+                    template.push('/');
+                    match segment {
+                        Some(literal) => template.push_str(&literal),
+                        None => template.push_str(&format!("{{p{}}}", index)),
+                    }
+                }
+                if trailing_slash {
+                    template.push('/');
+                }
+                template
+            })
+    }
+
+    fn parameter_value_strategy() -> impl proptest::strategy::Strategy<Value = String> {
+        use proptest::prelude::*;
+
+        prop_oneof!["[a-z0-9]{15,20}", "[a-c/%+~ é😀]{1,6}", "\\PC{1,8}",]
+    }
+
+    fn parameter_value_match_strategy(
+    ) -> impl proptest::strategy::Strategy<Value = ParameterValueMatch> {
+        use proptest::prelude::*;
+
+        prop_oneof![
+            Just(ParameterValueMatch::FirstAnchor),
+            Just(ParameterValueMatch::Shortest),
+            Just(ParameterValueMatch::Longest),
+        ]
+    }
+
+    // parse a path with a router that only has this route
+    fn parse_single_route(
+        template: &str,
+        path: &str,
+        maximum_parameter_value_length: usize,
+        parameter_value_match: ParameterValueMatch,
+    ) -> Option<HashMap<String, String>> {
+        let mut router = Router::new();
+        router
+            .set_maximum_parameter_value_length(maximum_parameter_value_length)
+            .set_parameter_value_match(parameter_value_match)
+            .insert_route((), template);
+
+        let (route_key, route_parameters) = router.parse_route(path).unwrap();
+        route_key?;
+
+        Some(
+            route_parameters
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value.into_owned()))
+                .collect(),
+        )
+    }
+
+    proptest::proptest! {
+        // every stringified path parses to a route. If that is the same route the parameters are
+        // the same, otherwise the route is shadowed by a route that is tried first. That route
+        // has to match the path on it's own with the same parameters. A shadowing parameter may
+        // contain a slash, like `/{a}/b` with `a` equal to `c/d` for `/c/d/b`, so the other route
+        // does not always stringify to the same path.
+        #[test]
+        fn router_round_trip(
+            templates in proptest::collection::vec(template_strategy(), 1..8),
+            parameter_values in proptest::collection::vec(parameter_value_strategy(), 4),
+            maximum_parameter_value_length in 1usize..40,
+            parameter_value_match in parameter_value_match_strategy(),
+        ) {
+            let mut templates = templates;
+            templates.sort();
+            templates.dedup();
+
+            let parameters: HashMap<_, _> = ["p0", "p1", "p2", "p3"]
+                .into_iter()
+                .zip(parameter_values.iter().map(|value| value.as_str()))
+                .collect();
+
+            let mut router = Router::new();
+            router
+                .set_maximum_parameter_value_length(maximum_parameter_value_length)
+                .set_parameter_value_match(parameter_value_match);
+            for template in templates.iter() {
+                router.insert_route(template.as_str(), template.as_str());
+            }
+
+            for template in templates.iter() {
+                let parameter_names: Vec<_> = TEMPLATE_PLACEHOLDER_REGEX
+                    .captures_iter(template)
+                    .map(|captures| captures.get(1).unwrap().as_str())
+                    .collect();
+
+                // the anchor after a value is only found if the value fits in the window
+                if parameter_names.iter().any(|parameter_name| {
+                    encode_component(parameters[parameter_name]).len()
+                        > maximum_parameter_value_length
+                }) {
+                    continue;
+                }
+
+                let path = router.stringify_route(&template.as_str(), &parameters).unwrap();
+                let (route_key, route_parameters) = router.parse_route(&path).unwrap();
+                proptest::prop_assert!(route_key.is_some(), "{} does not parse", path);
+                let route_key = route_key.unwrap();
+
+                let route_parameters: HashMap<_, _> = route_parameters
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_ref()))
+                    .collect();
+
+                let expected_parameters: HashMap<_, _> = parameter_names
+                    .iter()
+                    .map(|parameter_name| (*parameter_name, parameters[parameter_name]))
+                    .collect();
+                if *route_key == template.as_str() {
+                    proptest::prop_assert_eq!(&route_parameters, &expected_parameters);
+                } else {
+                    let route_parameters = route_parameters
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect();
+                    proptest::prop_assert_eq!(
+                        parse_single_route(
+                            route_key,
+                            &path,
+                            maximum_parameter_value_length,
+                            parameter_value_match,
+                        ),
+                        Some(route_parameters),
+                        "{} is shadowed by {}",
+                        path,
+                        route_key
+                    );
+                }
+
+                // and the route parses the path it stringified on it's own
+                let expected_parameters = expected_parameters
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect();
+                proptest::prop_assert_eq!(
+                    parse_single_route(
+                        template,
+                        &path,
+                        maximum_parameter_value_length,
+                        parameter_value_match,
+                    ),
+                    Some(expected_parameters)
+                );
+            }
+        }
+    }
+
+    proptest::proptest! {
+        // any path, including paths that are not valid templates, can be parsed without panicking
+        #[test]
+        fn router_parse_any_path(
+            path in "(/|é|😀|a|%|\\PC){0,24}",
+            maximum_parameter_value_length in 1usize..8,
+        ) {
+            let mut router = Router::new();
+            router
+                .set_maximum_parameter_value_length(maximum_parameter_value_length)
+                .set_trailing_slash(TrailingSlash::RedirectHint)
+                .set_decode_unreserved(true)
+                .insert_route("a", "/café/{x}/über")
+                .insert_route("b", "/café/{x}")
+                .insert_route("c", "/ü{x}ö/")
+                .insert_route("d", "/😀/{x}é{y}");
+
+            let _ = router.parse_route(&path);
+            let _ = router.parse_route_canonical(&path);
+
+            router.set_case_insensitive(true);
+            let _ = router.parse_route(&path);
+        }
+    }
 }
@@ -1,20 +1,190 @@
+use std::borrow::Cow;
 use std::cmp;
 
-pub fn find_common_prefix_length(chars_left: &Vec<char>, chars_right: &Vec<char>) -> usize {
-    let common_length = cmp::min(chars_left.len(), chars_right.len());
+// the length in bytes of the common prefix of both strings, this is always a char boundary in
+// both strings so it is safe to slice them with it
+pub fn find_common_prefix_length(left: &str, right: &str) -> usize {
+    left.char_indices()
+        .zip(right.chars())
+        .find(|((_index, char_left), char_right)| char_left != char_right)
+        .map(|((index, _char_left), _char_right)| index)
+        .unwrap_or_else(|| cmp::min(left.len(), right.len()))
+}
+
+// the largest char boundary in value that is not larger than index
+pub fn floor_char_boundary(value: &str, index: usize) -> usize {
+    if index >= value.len() {
+        return value.len();
+    }
+
+    let mut index = index;
+    while !value.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+pub fn starts_with_ignore_ascii_case(value: &str, prefix: &str) -> bool {
+    value.len() >= prefix.len()
+        && value.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+pub fn ends_with_ignore_ascii_case(value: &str, suffix: &str) -> bool {
+    value.len() >= suffix.len()
+        && value.as_bytes()[value.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
+}
+
+pub fn find_ignore_ascii_case(value: &str, pattern: &str) -> Option<usize> {
+    if pattern.len() > value.len() {
+        return None;
+    }
+
+    // only ascii bytes are case folded, so a match always starts at a char boundary
+    (0..=value.len() - pattern.len()).find(|index| {
+        value.as_bytes()[*index..*index + pattern.len()].eq_ignore_ascii_case(pattern.as_bytes())
+    })
+}
+
+pub fn collapse_duplicate_slashes(value: &str) -> Cow<'_, str> {
+    if !value.contains("//") {
+        return Cow::Borrowed(value);
+    }
+
+    let mut result = String::with_capacity(value.len());
+    let mut previous_char = None;
+    for current_char in value.chars() {
+        if current_char == '/' && previous_char == Some('/') {
+            continue;
+        }
+
+        result.push(current_char);
+        previous_char = Some(current_char);
+    }
 
+    Cow::Owned(result)
+}
+
+// decode percent escapes of unreserved characters (rfc 3986) and of non ascii characters,
+// escapes of reserved characters (like `%2F`) and of `%` itself are kept as they are so decoding
+// a parameter value later on still works
+pub fn decode_unreserved_percent_escapes(value: &str) -> Cow<'_, str> {
+    if !value.contains('%') {
+        return Cow::Borrowed(value);
+    }
+
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
     let mut index = 0;
-    while index < common_length {
-        let char_left = chars_left[index];
-        let char_right = chars_right[index];
-        if char_left != char_right {
-            break;
+    while index < bytes.len() {
+        if let Some(byte) = decode_percent_escape(bytes, index) {
+            if is_unreserved(byte) {
+                result.push(byte);
+                index += 3;
+                continue;
+            }
+
+            // a non ascii character is encoded as a sequence of escapes, only decode it if the
+            // whole sequence is valid utf-8
+            let length = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 0,
+            };
+            let sequence: Option<Vec<_>> = (0..length)
+                .map(|offset| decode_percent_escape(bytes, index + offset * 3))
+                .collect();
+            if let Some(sequence) = sequence
+                .filter(|sequence| !sequence.is_empty() && std::str::from_utf8(sequence).is_ok())
+            {
+                result.extend(sequence);
+                index += length * 3;
+                continue;
+            }
         }
 
+        result.push(bytes[index]);
         index += 1;
     }
 
-    index
+    Cow::Owned(String::from_utf8(result).unwrap())
+}
+
+pub fn contains_encoded_slash(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    (0..bytes.len()).any(|index| decode_percent_escape(bytes, index) == Some(b'/'))
+}
+
+// the shortest relative reference that resolves to `to_path` when it is resolved against
+// `from_path`, as described in rfc 3986. The reference only uses `..` segments to go up so it
+// keeps working when both paths are mounted under another path. If one of the paths is not an
+// absolute path, `to_path` is returned as it is.
+pub fn relative_reference(from_path: &str, to_path: &str) -> String {
+    if !from_path.starts_with('/') || !to_path.starts_with('/') {
+        return to_path.to_owned();
+    }
+
+    // the query and the fragment of the current location do not matter
+    let from_path = from_path.split(['?', '#']).next().unwrap();
+
+    let from_directory = &from_path[..from_path.rfind('/').unwrap() + 1];
+    let to_index = to_path.rfind('/').unwrap();
+    let to_directory = &to_path[..to_index + 1];
+    let to_name = &to_path[to_index + 1..];
+
+    let from_segments: Vec<_> = directory_segments(from_directory).collect();
+    let to_segments: Vec<_> = directory_segments(to_directory).collect();
+    let common_length = from_segments
+        .iter()
+        .zip(to_segments.iter())
+        .take_while(|(from_segment, to_segment)| from_segment == to_segment)
+        .count();
+
+    let mut reference = String::new();
+    for _ in common_length..from_segments.len() {
+        reference.push_str("../");
+    }
+    for to_segment in &to_segments[common_length..] {
+        reference.push_str(to_segment);
+        reference.push('/');
+    }
+    reference.push_str(to_name);
+
+    // an empty reference is the current location including it's name, a reference that starts
+    // with a slash is an absolute path and a colon in the first segment makes it look like a
+    // scheme
+    let first_segment = reference.split('/').next().unwrap();
+    if reference.is_empty() || first_segment.is_empty() || first_segment.contains(':') {
+        reference.insert_str(0, "./");
+    }
+
+    reference
+}
+
+// the segments of a directory path that starts and ends with a slash
+fn directory_segments(directory: &str) -> impl Iterator<Item = &str> {
+    let segments = &directory[1..];
+
+    segments
+        .strip_suffix('/')
+        .into_iter()
+        .flat_map(|segments| segments.split('/'))
+}
+
+fn decode_percent_escape(bytes: &[u8], index: usize) -> Option<u8> {
+    if bytes.get(index) != Some(&b'%') {
+        return None;
+    }
+
+    let high = (*bytes.get(index + 1)? as char).to_digit(16)?;
+    let low = (*bytes.get(index + 2)? as char).to_digit(16)?;
+
+    Some((high * 16 + low) as u8)
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
 }
 
 #[cfg(test)]
@@ -23,28 +193,104 @@ mod tests {
 
     #[test]
     fn common_prefix_length_test() {
+        assert_eq!(find_common_prefix_length("ab", "abc"), 2);
+        assert_eq!(find_common_prefix_length("abc", "abc"), 3);
+        assert_eq!(find_common_prefix_length("bc", "abc"), 0);
+
+        // the length is in bytes, and never splits a char
+        assert_eq!(find_common_prefix_length("/café/", "/café"), 6);
+        assert_eq!(find_common_prefix_length("/é", "/è"), 1);
+        assert_eq!(find_common_prefix_length("😀a", "😀b"), 4);
+    }
+
+    #[test]
+    fn floor_char_boundary_test() {
+        assert_eq!(floor_char_boundary("abc", 2), 2);
+        assert_eq!(floor_char_boundary("abc", 5), 3);
+        assert_eq!(floor_char_boundary("aé", 2), 1);
+        assert_eq!(floor_char_boundary("a😀", 4), 1);
+        assert_eq!(floor_char_boundary("a😀", 5), 5);
+    }
+
+    #[test]
+    fn ignore_ascii_case_test() {
+        assert!(starts_with_ignore_ascii_case("/Product/1", "/product/"));
+        assert!(!starts_with_ignore_ascii_case("/Prod", "/product/"));
+        assert!(starts_with_ignore_ascii_case("/CAFé/1", "/café"));
+        assert!(!starts_with_ignore_ascii_case("/CAFÉ/1", "/café"));
+
+        assert!(ends_with_ignore_ascii_case("/a.JSON", ".json"));
+        assert!(!ends_with_ignore_ascii_case("json", ".json"));
+
+        assert_eq!(find_ignore_ascii_case("ab.JSON", ".json"), Some(2));
+        assert_eq!(find_ignore_ascii_case("é.json", ".json"), Some(2));
+        assert_eq!(find_ignore_ascii_case("ab", ".json"), None);
+    }
+
+    #[test]
+    fn collapse_duplicate_slashes_test() {
+        assert_eq!(collapse_duplicate_slashes("/a/b/"), Cow::Borrowed("/a/b/"));
+        assert_eq!(collapse_duplicate_slashes("//a///b//"), "/a/b/");
+    }
+
+    #[test]
+    fn decode_unreserved_percent_escapes_test() {
         assert_eq!(
-            find_common_prefix_length(
-                &String::from("ab").chars().collect(),
-                &String::from("abc").chars().collect()
-            ),
-            2
+            decode_unreserved_percent_escapes("/menu"),
+            Cow::Borrowed("/menu")
+        );
+        assert_eq!(
+            decode_unreserved_percent_escapes("/caf%C3%A9/%7euser"),
+            "/café/~user"
         );
-
         assert_eq!(
-            find_common_prefix_length(
-                &String::from("abc").chars().collect(),
-                &String::from("abc").chars().collect()
-            ),
-            3
+            decode_unreserved_percent_escapes("/a%2Fb/100%25/%41"),
+            "/a%2Fb/100%25/A"
         );
+        assert_eq!(
+            decode_unreserved_percent_escapes("/%C3%28/%C3"),
+            "/%C3%28/%C3"
+        );
+        assert_eq!(
+            decode_unreserved_percent_escapes("/%zz/%/%A9"),
+            "/%zz/%/%A9"
+        );
+    }
 
+    #[test]
+    fn contains_encoded_slash_test() {
+        assert!(contains_encoded_slash("/a%2Fb"));
+        assert!(contains_encoded_slash("/a%2fb"));
+        assert!(!contains_encoded_slash("/a/b%25"));
+    }
+
+    #[test]
+    fn relative_reference_test() {
+        assert_eq!(relative_reference("/product/1", "/product/2"), "2");
+        assert_eq!(
+            relative_reference("/product/1/reviews", "/product/2"),
+            "../2"
+        );
+        assert_eq!(
+            relative_reference("/product/1/reviews", "/product/1/reviews/3"),
+            "reviews/3"
+        );
+        assert_eq!(
+            relative_reference("/product/1/", "/category/2/"),
+            "../../category/2/"
+        );
+        assert_eq!(relative_reference("/product/1/", "/product/1/"), "./");
+        assert_eq!(relative_reference("/product/1", "/product/"), "./");
+        assert_eq!(relative_reference("/product/1", "/product/1"), "1");
+        assert_eq!(relative_reference("/product/1", "/"), "../");
+        assert_eq!(relative_reference("/", "/product/1"), "product/1");
         assert_eq!(
-            find_common_prefix_length(
-                &String::from("bc").chars().collect(),
-                &String::from("abc").chars().collect()
-            ),
-            0,
+            relative_reference("/product/1?page=2#top", "/product/2"),
+            "2"
         );
+        assert_eq!(relative_reference("/a", "/b:c"), "./b:c");
+        assert_eq!(relative_reference("/a", "//b"), ".//b");
+        assert_eq!(relative_reference("a", "/b"), "/b");
+        assert_eq!(relative_reference("/a", "b"), "b");
     }
 }
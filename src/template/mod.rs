@@ -1,8 +1,9 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-pub mod template_pairs;
-pub mod template_parts;
+pub(crate) mod template_pairs;
+pub(crate) mod template_parts;
 
+// the default parameter placeholder of a router, like `{id}`
 pub static TEMPLATE_PLACEHOLDER_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\{(.*?)\}").unwrap());
@@ -1,4 +1,4 @@
-use super::template_parts::{parse_template_parts, TemplateParts};
+use super::template_parts::{parse_template_parts, TemplatePart, TemplateParts};
 use regex::Regex;
 
 pub fn parse_template_pairs<'r>(template: &'r str, re: &'r Regex) -> TemplatePairs<'r> {
@@ -54,6 +54,40 @@ impl<'r> Iterator for TemplatePairs<'r> {
     }
 }
 
+// turn expanded template parts into pairs. Unlike the pairs from `parse_template_pairs` a literal
+// may be followed by another literal, the route nodes for these are merged like any other node.
+pub fn template_parts_to_pairs<'r>(parts: &[TemplatePart<'r>]) -> Vec<(&'r str, Option<&'r str>)> {
+    let mut pairs = Vec::new();
+    let mut parameter = None;
+
+    for part in parts {
+        match *part {
+            TemplatePart::Literal(anchor) => {
+                // empty literals are only needed as the first anchor, a parameter is closed by
+                // the next literal that is not empty or by the end of the template
+                if anchor.is_empty() && !pairs.is_empty() {
+                    continue;
+                }
+                pairs.push((anchor, parameter.take()));
+            }
+            TemplatePart::Parameter(name, _encoding) => {
+                if pairs.is_empty() {
+                    pairs.push(("", None));
+                }
+                if let Some(previous) = parameter.replace(name) {
+                    pairs.push(("", Some(previous)));
+                }
+            }
+        }
+    }
+
+    if let Some(parameter) = parameter {
+        pairs.push(("", Some(parameter)));
+    }
+
+    pairs
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::TEMPLATE_PLACEHOLDER_REGEX;
@@ -81,4 +115,33 @@ mod tests {
 
         assert_eq!(pairs, vec![("", None)])
     }
+
+    #[test]
+    fn template_parts_to_pairs_test() {
+        use super::super::template_parts::parse_template_expression_parts;
+
+        let parts = parse_template_expression_parts("/a/{b}/{c}", &TEMPLATE_PLACEHOLDER_REGEX);
+
+        assert_eq!(
+            template_parts_to_pairs(&parts),
+            parse_template_pairs("/a/{b}/{c}", &TEMPLATE_PLACEHOLDER_REGEX).collect::<Vec<_>>()
+        );
+
+        let parts = parse_template_expression_parts("/a{/b}{?c,d}", &TEMPLATE_PLACEHOLDER_REGEX);
+
+        assert_eq!(
+            template_parts_to_pairs(&parts),
+            vec![
+                ("/a", None),
+                ("/", None),
+                ("?", Some("b")),
+                ("c", None),
+                ("=", None),
+                ("&", Some("c")),
+                ("d", None),
+                ("=", None),
+                ("", Some("d"))
+            ]
+        );
+    }
 }
@@ -35,6 +35,7 @@ impl<'r> TemplateParts<'r> {
 impl<'r> Iterator for TemplateParts<'r> {
     type Item = &'r str;
 
+    #[allow(clippy::manual_is_multiple_of)]
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_finished {
             return None;
@@ -66,6 +67,104 @@ impl<'r> Iterator for TemplateParts<'r> {
     }
 }
 
+// a part of a template where placeholders may be rfc 6570 expressions, the expressions are
+// expanded into literals and plain parameters so the route nodes never see an operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplatePart<'r> {
+    Literal(&'r str),
+    Parameter(&'r str, TemplateParameterEncoding),
+}
+
+// how the value of a parameter is encoded when stringifying a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateParameterEncoding {
+    // the default, everything but unreserved characters is encoded
+    Component,
+    // `{+name}` and `{#name}`, reserved characters like `/` are not encoded
+    Reserved,
+    // `{/name*}`, the value is a list of path segments, slashes are not encoded
+    Segments,
+}
+
+pub fn parse_template_expression_parts<'r>(
+    template: &'r str,
+    re: &'r Regex,
+) -> Vec<TemplatePart<'r>> {
+    let mut parts = Vec::new();
+    let mut offset = 0;
+
+    for current_match in re.captures_iter(template) {
+        let first_capture = current_match.get(0).unwrap();
+        let current_capture = current_match.get(1).unwrap();
+
+        parts.push(TemplatePart::Literal(
+            &template[offset..first_capture.start()],
+        ));
+        parts.append(&mut expand_template_expression(current_capture.as_str()));
+
+        offset = first_capture.end();
+    }
+    parts.push(TemplatePart::Literal(&template[offset..]));
+
+    parts
+}
+
+// expand an rfc 6570 level 3 expression, with the explode modifier from level 4. Undefined
+// variables are not supported, every variable in the expression is a required parameter. The
+// expansion of `{?a,b}` is `?a={a}&b={b}`, so query parameters have to be in template order.
+fn expand_template_expression(expression: &str) -> Vec<TemplatePart<'_>> {
+    let (operator, variables) = match expression.chars().next() {
+        Some(operator @ ('+' | '#' | '.' | '/' | ';' | '?' | '&')) => {
+            (Some(operator), &expression[1..])
+        }
+        _ => (None, expression),
+    };
+
+    // the first prefix, the separator between variables, if the variables are named and if
+    // reserved characters are allowed, see the table in appendix A of the rfc
+    let (first, separator, named, reserved) = match operator {
+        Some('+') => ("", ",", false, true),
+        Some('#') => ("#", ",", false, true),
+        Some('.') => (".", ".", false, false),
+        Some('/') => ("/", "/", false, false),
+        Some(';') => (";", ";", true, false),
+        Some('?') => ("?", "&", true, false),
+        Some('&') => ("&", "&", true, false),
+        _ => ("", ",", false, false),
+    };
+
+    let mut parts = Vec::new();
+    for (index, variable) in variables.split(',').enumerate() {
+        let (name, explode) = match variable.strip_suffix('*') {
+            Some(name) => (name, true),
+            None => (variable, false),
+        };
+
+        parts.push(TemplatePart::Literal(if index == 0 {
+            first
+        } else {
+            separator
+        }));
+        if named {
+            parts.push(TemplatePart::Literal(name));
+            parts.push(TemplatePart::Literal("="));
+        }
+
+        // exploding a list only makes a difference for path segments, for the other operators
+        // the value is a string that is expanded as is
+        let encoding = if reserved {
+            TemplateParameterEncoding::Reserved
+        } else if explode && operator == Some('/') {
+            TemplateParameterEncoding::Segments
+        } else {
+            TemplateParameterEncoding::Component
+        };
+        parts.push(TemplatePart::Parameter(name, encoding));
+    }
+
+    parts
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::TEMPLATE_PLACEHOLDER_REGEX;
@@ -87,4 +186,52 @@ mod tests {
 
         assert_eq!(parts, vec![""])
     }
+
+    #[test]
+    fn parse_template_expression_parts_test() {
+        use TemplateParameterEncoding::*;
+        use TemplatePart::*;
+
+        let parts = parse_template_expression_parts("/a/{b}", &TEMPLATE_PLACEHOLDER_REGEX);
+
+        assert_eq!(
+            parts,
+            vec![
+                Literal("/a/"),
+                Literal(""),
+                Parameter("b", Component),
+                Literal("")
+            ]
+        );
+
+        let parts = parse_template_expression_parts(
+            "/files{+path}{/segments*}{?q,page}{#frag}",
+            &TEMPLATE_PLACEHOLDER_REGEX,
+        );
+
+        assert_eq!(
+            parts,
+            vec![
+                Literal("/files"),
+                Literal(""),
+                Parameter("path", Reserved),
+                Literal(""),
+                Literal("/"),
+                Parameter("segments", Segments),
+                Literal(""),
+                Literal("?"),
+                Literal("q"),
+                Literal("="),
+                Parameter("q", Component),
+                Literal("&"),
+                Literal("page"),
+                Literal("="),
+                Parameter("page", Component),
+                Literal(""),
+                Literal("#"),
+                Parameter("frag", Reserved),
+                Literal(""),
+            ]
+        );
+    }
 }
@@ -0,0 +1,177 @@
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTrace<'r, 'f, K> {
+    pub steps: Vec<ParseTraceStep<'r, 'f, K>>,
+    depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTraceStep<'r, 'f, K> {
+    // depth of the node in the trie, the root node has depth 0
+    pub depth: usize,
+    // anchor of the node
+    pub anchor: &'r str,
+    // does the node have a parameter
+    pub has_parameter: bool,
+    // the part of the path that is left to match when the step was recorded
+    pub path: Cow<'f, str>,
+    // what happened
+    pub outcome: ParseTraceOutcome<'f, K>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTraceOutcome<'f, K> {
+    // the path starts with the anchor, the children will be tried next
+    AnchorMatched,
+    // the anchor was found in the window, everything before it is the parameter value
+    ParameterMatched {
+        window: Cow<'f, str>,
+        value: Cow<'f, str>,
+    },
+    // the path does not start with the anchor
+    AnchorMismatch,
+    // there is no path left for the parameter value
+    EmptyParameterValue,
+    // the anchor was not found in the window, the window is limited by the maximum parameter
+    // value length
+    AnchorNotFound {
+        window: Cow<'f, str>,
+    },
+    // the whole path is matched and this node has a route
+    RouteMatched(K),
+    // the route of this node would match if a trailing slash was added to or removed from the
+    // path, this is only used if there is no exact match
+    TrailingSlashMismatch(K),
+    // none of the children matched and there is still some path left
+    PathRemaining(Cow<'f, str>),
+    // the whole path is matched but this node has no route
+    NoRoute,
+    // this node was already tried with the same path while backtracking, the result of that
+    // try is used again
+    AlreadyTried,
+}
+
+impl<'r, 'f, K> ParseTrace<'r, 'f, K> {
+    pub(crate) fn record(
+        &mut self,
+        anchor: &'r str,
+        has_parameter: bool,
+        path: &'f str,
+        outcome: ParseTraceOutcome<'f, K>,
+    ) {
+        self.steps.push(ParseTraceStep {
+            depth: self.depth,
+            anchor,
+            has_parameter,
+            path: Cow::Borrowed(path),
+            outcome,
+        });
+    }
+
+    // add the steps of a trace that does not borrow from the path, like the trace of a path that
+    // was normalized before it was matched
+    pub(crate) fn append<'g>(&mut self, trace: ParseTrace<'r, 'g, K>) {
+        let steps = trace.steps.into_iter().map(|step| ParseTraceStep {
+            depth: self.depth + step.depth,
+            anchor: step.anchor,
+            has_parameter: step.has_parameter,
+            path: Cow::Owned(step.path.into_owned()),
+            outcome: step.outcome.into_owned(),
+        });
+        self.steps.extend(steps);
+    }
+
+    pub(crate) fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+impl<'r, 'f, K> Default for ParseTrace<'r, 'f, K> {
+    fn default() -> Self {
+        Self {
+            steps: Default::default(),
+            depth: Default::default(),
+        }
+    }
+}
+
+impl<'f, K> ParseTraceOutcome<'f, K> {
+    fn into_owned<'g>(self) -> ParseTraceOutcome<'g, K> {
+        match self {
+            ParseTraceOutcome::AnchorMatched => ParseTraceOutcome::AnchorMatched,
+            ParseTraceOutcome::ParameterMatched { window, value } => {
+                ParseTraceOutcome::ParameterMatched {
+                    window: Cow::Owned(window.into_owned()),
+                    value: Cow::Owned(value.into_owned()),
+                }
+            }
+            ParseTraceOutcome::AnchorMismatch => ParseTraceOutcome::AnchorMismatch,
+            ParseTraceOutcome::EmptyParameterValue => ParseTraceOutcome::EmptyParameterValue,
+            ParseTraceOutcome::AnchorNotFound { window } => ParseTraceOutcome::AnchorNotFound {
+                window: Cow::Owned(window.into_owned()),
+            },
+            ParseTraceOutcome::RouteMatched(route_key) => {
+                ParseTraceOutcome::RouteMatched(route_key)
+            }
+            ParseTraceOutcome::TrailingSlashMismatch(route_key) => {
+                ParseTraceOutcome::TrailingSlashMismatch(route_key)
+            }
+            ParseTraceOutcome::PathRemaining(path) => {
+                ParseTraceOutcome::PathRemaining(Cow::Owned(path.into_owned()))
+            }
+            ParseTraceOutcome::NoRoute => ParseTraceOutcome::NoRoute,
+            ParseTraceOutcome::AlreadyTried => ParseTraceOutcome::AlreadyTried,
+        }
+    }
+}
+
+impl<'f, K: fmt::Debug> fmt::Display for ParseTraceOutcome<'f, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTraceOutcome::AnchorMatched => write!(f, "anchor matched"),
+            ParseTraceOutcome::ParameterMatched { window, value } => {
+                write!(f, "parameter value {:?} (window {:?})", value, window)
+            }
+            ParseTraceOutcome::AnchorMismatch => write!(f, "anchor mismatch"),
+            ParseTraceOutcome::EmptyParameterValue => write!(f, "empty parameter value"),
+            ParseTraceOutcome::AnchorNotFound { window } => {
+                write!(f, "anchor not found (window {:?})", window)
+            }
+            ParseTraceOutcome::RouteMatched(route_key) => {
+                write!(f, "route matched {:?}", route_key)
+            }
+            ParseTraceOutcome::TrailingSlashMismatch(route_key) => {
+                write!(
+                    f,
+                    "route {:?} matches with a trailing slash toggled",
+                    route_key
+                )
+            }
+            ParseTraceOutcome::PathRemaining(path) => {
+                write!(f, "no child matched, {:?} remaining", path)
+            }
+            ParseTraceOutcome::NoRoute => write!(f, "no route"),
+            ParseTraceOutcome::AlreadyTried => write!(f, "already tried"),
+        }
+    }
+}
+
+impl<'r, 'f, K: fmt::Debug> fmt::Display for ParseTrace<'r, 'f, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in self.steps.iter() {
+            write!(f, "{}", "  ".repeat(step.depth))?;
+            if step.has_parameter {
+                write!(f, "{{}}")?;
+            }
+            writeln!(f, "{:?} at {:?}: {}", step.anchor, step.path, step.outcome)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+use crate::router::Router;
+use crate::template::template_parts::TemplateParameterEncoding;
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypescriptError {
+    // a parameter value is encoded by a custom encoder, generated code cannot do the same
+    CustomEncoder {
+        route_key: String,
+        parameter_name: String,
+    },
+}
+
+impl fmt::Display for TypescriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypescriptError::CustomEncoder {
+                route_key,
+                parameter_name,
+            } => write!(
+                f,
+                "parameter {} of route {} has a custom encoder",
+                parameter_name, route_key
+            ),
+        }
+    }
+}
+
+impl Error for TypescriptError {}
+
+// generate a typescript module with the parameter types of every route and a typed
+// `stringifyRoute` function. The route keys are the `Display` of the keys in the router.
+// Parameter values are encoded like `stringify_route` does, for routes with rfc 6570 operators
+// as well. Fails if the router has a custom encoder for a parameter of a route.
+pub fn generate_typescript<K: Eq + Hash + Clone + Display, M>(
+    router: &Router<K, M>,
+) -> Result<String, TypescriptError> {
+    let route_templates = router.routes();
+
+    let mut route_stringifiers = Vec::new();
+    for (route_key, _template, _route_parameter_names) in route_templates.iter() {
+        let template_pairs = router.route_template_pairs(route_key).unwrap();
+        route_stringifiers.push(stringifier(router, *route_key, &template_pairs)?);
+    }
+
+    let mut module = String::new();
+
+    writeln!(module, "// generated by goodrouter, do not edit").unwrap();
+    writeln!(module).unwrap();
+
+    writeln!(module, "export interface RouteParameters {{").unwrap();
+    for (route_key, _template, route_parameter_names) in route_templates.iter() {
+        let route_key = quote(&route_key.to_string());
+        if route_parameter_names.is_empty() {
+            writeln!(module, "    {}: Record<string, never>;", route_key).unwrap();
+        } else {
+            let fields: Vec<_> = route_parameter_names
+                .iter()
+                .map(|parameter_name| format!("{}: string", quote(parameter_name)))
+                .collect();
+            writeln!(module, "    {}: {{ {} }};", route_key, fields.join("; ")).unwrap();
+        }
+    }
+    writeln!(module, "}}").unwrap();
+    writeln!(module).unwrap();
+
+    writeln!(module, "export type RouteKey = keyof RouteParameters;").unwrap();
+    writeln!(module).unwrap();
+
+    writeln!(
+        module,
+        "export const routeTemplates: {{ [K in RouteKey]: string }} = {{"
+    )
+    .unwrap();
+    for (route_key, template, _route_parameter_names) in route_templates.iter() {
+        writeln!(
+            module,
+            "    {}: {},",
+            quote(&route_key.to_string()),
+            quote(template)
+        )
+        .unwrap();
+    }
+    writeln!(module, "}};").unwrap();
+    writeln!(module).unwrap();
+
+    // the encoders of the codec module, `encodeURIComponent` does not encode `!'()*`
+    writeln!(
+        module,
+        "const parameterEncoders: {{ [encoding: string]: (value: string) => string }} = {{"
+    )
+    .unwrap();
+    writeln!(
+        module,
+        r#"    component: (value) => encodeURIComponent(value).replace(/[!'()*]/g, (character) => "%" + character.charCodeAt(0).toString(16).toUpperCase()),"#
+    )
+    .unwrap();
+    writeln!(
+        module,
+        r#"    reserved: (value) => value.replace(/%[0-9A-Fa-f]{{2}}|[^A-Za-z0-9._~:\/?#\[\]@!$&'()*+,;=-]/gu, (match) => match.length === 3 ? match : parameterEncoders.component(match)),"#
+    )
+    .unwrap();
+    writeln!(
+        module,
+        r#"    segments: (value) => value.split("/").map(parameterEncoders.component).join("/"),"#
+    )
+    .unwrap();
+    writeln!(module, "}};").unwrap();
+    writeln!(module).unwrap();
+
+    writeln!(
+        module,
+        "export const routeStringifiers: {{ [K in RouteKey]: (routeParameters: RouteParameters[K]) => string }} = {{"
+    )
+    .unwrap();
+    for ((route_key, _template, _route_parameter_names), route_stringifier) in
+        route_templates.iter().zip(route_stringifiers.iter())
+    {
+        writeln!(
+            module,
+            "    {}: {},",
+            quote(&route_key.to_string()),
+            route_stringifier
+        )
+        .unwrap();
+    }
+    writeln!(module, "}};").unwrap();
+    writeln!(module).unwrap();
+
+    writeln!(
+        module,
+        "export function stringifyRoute<K extends RouteKey>(routeKey: K, routeParameters: RouteParameters[K]): string {{"
+    )
+    .unwrap();
+    if router.collapses_duplicate_slashes() {
+        // the router would collapse the slashes when parsing, so the path would not parse to the
+        // same parameters. `stringify_route` has no path in that case.
+        writeln!(
+            module,
+            "    const path = routeStringifiers[routeKey](routeParameters);"
+        )
+        .unwrap();
+        writeln!(module, r#"    if (path.includes("//")) {{"#).unwrap();
+        writeln!(
+            module,
+            r#"        throw new Error("duplicate slashes in path " + path);"#
+        )
+        .unwrap();
+        writeln!(module, "    }}").unwrap();
+        writeln!(module, "    return path;").unwrap();
+    } else {
+        writeln!(
+            module,
+            "    return routeStringifiers[routeKey](routeParameters);"
+        )
+        .unwrap();
+    }
+    writeln!(module, "}}").unwrap();
+
+    Ok(module)
+}
+
+// an arrow function that concatenates the encoded parameter values and the anchors of a route
+fn stringifier<K: Eq + Hash + Clone + Display, M>(
+    router: &Router<K, M>,
+    route_key: &K,
+    template_pairs: &[(&str, Option<&str>)],
+) -> Result<String, TypescriptError> {
+    let mut terms = Vec::new();
+    let mut has_parameters = false;
+    // the anchors of nodes that are split in the trie are joined again
+    let mut literal = String::new();
+
+    for (anchor, parameter) in template_pairs.iter().cloned() {
+        if let Some(parameter_name) = parameter {
+            if !literal.is_empty() {
+                terms.push(quote(&literal));
+                literal.clear();
+            }
+
+            let encoder = match router.parameter_value_encoding(route_key, parameter_name) {
+                Some(TemplateParameterEncoding::Component) => "component",
+                Some(TemplateParameterEncoding::Reserved) => "reserved",
+                Some(TemplateParameterEncoding::Segments) => "segments",
+                None => {
+                    return Err(TypescriptError::CustomEncoder {
+                        route_key: route_key.to_string(),
+                        parameter_name: parameter_name.to_owned(),
+                    })
+                }
+            };
+            terms.push(format!(
+                "parameterEncoders.{}(routeParameters[{}])",
+                encoder,
+                quote(parameter_name)
+            ));
+            has_parameters = true;
+        }
+
+        literal.push_str(anchor);
+    }
+    if !literal.is_empty() || terms.is_empty() {
+        terms.push(quote(&literal));
+    }
+
+    if has_parameters {
+        Ok(format!("(routeParameters) => {}", terms.join(" + ")))
+    } else {
+        Ok(format!("() => {}", terms.join(" + ")))
+    }
+}
+
+// a javascript string literal
+fn quote(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+
+    literal.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // control characters, and line and paragraph separators that are not allowed in
+            // string literals before es2019
+            character
+                if character.is_control() || character == '\u{2028}' || character == '\u{2029}' =>
+            {
+                write!(literal, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => literal.push(character),
+        }
+    }
+    literal.push('"');
+
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec;
+    use regex::Regex;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::io::Write as _;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn generate_typescript_test() {
+        let mut router = Router::new();
+        router
+            .insert_route("users", "/users")
+            .insert_route("user", "/users/{id}")
+            .insert_route("post", "/users/{id}/posts/{post}/");
+
+        assert_eq!(
+            generate_typescript(&router).unwrap(),
+            [
+                "// generated by goodrouter, do not edit",
+                "",
+                "export interface RouteParameters {",
+                r#"    "users": Record<string, never>;"#,
+                r#"    "user": { "id": string };"#,
+                r#"    "post": { "id": string; "post": string };"#,
+                "}",
+                "",
+                "export type RouteKey = keyof RouteParameters;",
+                "",
+                "export const routeTemplates: { [K in RouteKey]: string } = {",
+                r#"    "users": "/users","#,
+                r#"    "user": "/users/{id}","#,
+                r#"    "post": "/users/{id}/posts/{post}/","#,
+                "};",
+                "",
+                "const parameterEncoders: { [encoding: string]: (value: string) => string } = {",
+                r#"    component: (value) => encodeURIComponent(value).replace(/[!'()*]/g, (character) => "%" + character.charCodeAt(0).toString(16).toUpperCase()),"#,
+                r#"    reserved: (value) => value.replace(/%[0-9A-Fa-f]{2}|[^A-Za-z0-9._~:\/?#\[\]@!$&'()*+,;=-]/gu, (match) => match.length === 3 ? match : parameterEncoders.component(match)),"#,
+                r#"    segments: (value) => value.split("/").map(parameterEncoders.component).join("/"),"#,
+                "};",
+                "",
+                "export const routeStringifiers: { [K in RouteKey]: (routeParameters: RouteParameters[K]) => string } = {",
+                r#"    "users": () => "/users","#,
+                r#"    "user": (routeParameters) => "/users/" + parameterEncoders.component(routeParameters["id"]),"#,
+                r#"    "post": (routeParameters) => "/users/" + parameterEncoders.component(routeParameters["id"]) + "/posts/" + parameterEncoders.component(routeParameters["post"]) + "/","#,
+                "};",
+                "",
+                "export function stringifyRoute<K extends RouteKey>(routeKey: K, routeParameters: RouteParameters[K]): string {",
+                "    return routeStringifiers[routeKey](routeParameters);",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn generate_typescript_custom_encoder() {
+        let mut router = Router::new();
+        router
+            .insert_route("user", "/users/{id}")
+            .insert_route("file", "/files/{path}")
+            .set_route_parameter_value_encoder("file", Box::new(codec::encode_segments));
+
+        assert_eq!(
+            generate_typescript(&router),
+            Err(TypescriptError::CustomEncoder {
+                route_key: "file".to_owned(),
+                parameter_name: "path".to_owned(),
+            })
+        );
+
+        let mut router = Router::new();
+        router
+            .insert_route("user", "/users/{id}")
+            .set_parameter_value_encoder(Box::new(codec::encode_form));
+
+        assert!(generate_typescript(&router).is_err());
+    }
+
+    // the generated module stringifies routes to the same paths as the router. This runs the
+    // module with node, run it with `cargo test -- --ignored` where node is installed.
+    #[test]
+    #[ignore = "needs node"]
+    fn generate_typescript_parity() {
+        let mut router = Router::new();
+        router
+            .set_template_operators(true)
+            .set_collapse_duplicate_slashes(true)
+            .insert_route("home", "/")
+            .insert_route("user", "/users/{id}")
+            .insert_route("file", "/files{+path}")
+            .insert_route("tree", "/tree{/segments*}{#line}")
+            .insert_route("search", "/search{?query,page}");
+
+        let cases = [
+            ("home", vec![]),
+            ("user", vec![("id", "a(b)!")]),
+            ("user", vec![("id", "x/y z*'~\u{e9}\u{1f600}")]),
+            ("file", vec![("path", "/x/y")]),
+            ("file", vec![("path", "/a b/%20%zz[\u{e9}]<\"|\\>")]),
+            (
+                "tree",
+                vec![("segments", "src/a b(1).rs"), ("line", "L1:2")],
+            ),
+            ("tree", vec![("segments", "a//b"), ("line", "L1")]),
+            ("search", vec![("query", "a&b=c"), ("page", "2")]),
+        ];
+
+        let mut script = strip_types(&generate_typescript(&router).unwrap());
+        script.push_str(
+            "function call(stringify) { try { return stringify(); } catch (error) { return \"<none>\"; } }\n",
+        );
+
+        let mut paths_expected = Vec::new();
+        for (route_key, route_parameters) in cases.iter() {
+            let route_parameters: HashMap<_, _> = route_parameters.iter().cloned().collect();
+            let path = router.stringify_route(route_key, &route_parameters);
+            paths_expected.push(
+                path.map(Cow::into_owned)
+                    .unwrap_or_else(|_| "<none>".to_owned()),
+            );
+
+            let fields: Vec<_> = route_parameters
+                .iter()
+                .map(|(name, value)| format!("{}: {}", quote(name), quote(value)))
+                .collect();
+            writeln!(
+                script,
+                "console.log(call(() => stringifyRoute({}, {{ {} }})));",
+                quote(route_key),
+                fields.join(", ")
+            )
+            .unwrap();
+        }
+
+        let mut child = Command::new("node")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("node is not installed");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", script);
+
+        let paths: Vec<_> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|path| path.to_owned())
+            .collect();
+        assert_eq!(paths, paths_expected);
+        assert_eq!(paths[1], "/users/a%28b%29%21");
+        assert_eq!(paths[3], "/files/x/y");
+        assert_eq!(paths[6], "<none>");
+    }
+
+    // turn the generated module into javascript, only the type annotations that the generator
+    // writes are removed
+    fn strip_types(module: &str) -> String {
+        let declaration_re = Regex::new(r"^(?:export )?const (\w+): .* = \{$").unwrap();
+
+        let mut script = String::new();
+        let mut in_interface = false;
+        for line in module.lines() {
+            if line.starts_with("export interface ") {
+                in_interface = true;
+            } else if in_interface {
+                in_interface = line != "}";
+            } else if line.starts_with("export type ") {
+            } else if let Some(captures) = declaration_re.captures(line) {
+                writeln!(script, "const {} = {{", &captures[1]).unwrap();
+            } else if line.starts_with("export function stringifyRoute") {
+                writeln!(
+                    script,
+                    "function stringifyRoute(routeKey, routeParameters) {{"
+                )
+                .unwrap();
+            } else {
+                writeln!(script, "{}", line).unwrap();
+            }
+        }
+
+        script
+    }
+
+    #[test]
+    fn quote_test() {
+        assert_eq!(quote(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(quote("a\nb\u{1}"), r#""a\nb\u0001""#);
+    }
+}